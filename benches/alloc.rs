@@ -0,0 +1,91 @@
+//! Counts heap allocations per `ZKPSystem::verify` on the 2048-bit group, with
+//! and without precomputed tables.
+//!
+//! Run with `cargo bench --bench alloc`. A counting global allocator wraps the
+//! system one, so the numbers include every intermediate `BigUint`.
+//!
+//! Reusing the `modpow_multi` table per thread took the plain path from about
+//! 2090 to 2030 allocations. Nearly all of the rest are the products and
+//! remainders of the squarings and multiplications themselves, which
+//! `num-bigint` always allocates afresh; pooling cannot reach those without
+//! doing the limb arithmetic by hand.
+
+use chaum_pedersen_auth::{ZKPProtocol, ZKPSystem, ZKPUtils};
+use num_bigint::BigUint;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const ITERATIONS: usize = 20;
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Returns the average allocations and allocated bytes of one `f` call.
+fn count<F: FnMut() -> bool>(mut f: F) -> (usize, usize) {
+    // warm up thread-local scratch space so it is not counted
+    black_box(f());
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = BYTES.load(Ordering::Relaxed);
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    (
+        (ALLOCATIONS.load(Ordering::Relaxed) - allocations) / ITERATIONS,
+        (BYTES.load(Ordering::Relaxed) - bytes) / ITERATIONS,
+    )
+}
+
+fn main() {
+    let (alpha, beta, p, q) = ZKPUtils::get_2048_bit_constants();
+    let plain = ZKPSystem::new(p.clone(), q.clone(), alpha.clone(), beta.clone());
+    let precomputed = ZKPSystem::builder()
+        .with_prime(p)
+        .with_order(q.clone())
+        .with_generator(alpha)
+        .with_second_generator(beta)
+        .with_precomputation()
+        .build()
+        .unwrap();
+
+    // Fixed values, so that runs before and after a change are comparable:
+    // the allocation count depends on the exponents.
+    let secret = BigUint::from(0x0123_4567_89ab_cdefu64).pow(4) % &q;
+    let k = BigUint::from(0xfedc_ba98_7654_3210u64).pow(4) % &q;
+    let c = BigUint::from(0x1f2e_3d4c_5b6a_7988u64).pow(4) % &q;
+    let (y1, y2) = &plain.compute_public_values(&secret);
+    let (r1, r2) = plain.compute_commitments(&k);
+    let s = plain.compute_response(&k, &c, &secret);
+
+    println!("2048-bit group, {} iterations", ITERATIONS);
+    for (name, system) in [("plain", &plain), ("precomputed", &precomputed)] {
+        let (allocations, bytes) = count(|| system.verify((&r1, &r2), &c, &s, (y1, y2)));
+        println!(
+            "  verify ({:<11}) {:>6} allocations {:>9} bytes",
+            name, allocations, bytes
+        );
+    }
+}
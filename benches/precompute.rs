@@ -0,0 +1,57 @@
+//! Times `FixedBaseTable::pow` across window widths and modulus sizes, to pick
+//! the defaults in `FixedBaseTable::window_for_modulus_bits`.
+//!
+//! Run with `cargo bench --bench precompute`. Exponents are 256 bits, the size
+//! of `q` in the larger groups. The 4096-bit modulus is a random odd number,
+//! which is fine for timing as in the `verify` bench.
+
+use chaum_pedersen_auth::{FixedBaseTable, ZKPUtils};
+use num_bigint::{BigUint, RandBigInt};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 50;
+const EXPONENT_BITS: u64 = 256;
+
+fn time<F: FnMut() -> BigUint>(mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let mut rng = rand::thread_rng();
+    let p4096 = rng.gen_biguint(4096) | BigUint::from(1u32) | (BigUint::from(1u32) << 4095);
+    let moduli = [
+        ZKPUtils::get_1024_bit_constants().2,
+        ZKPUtils::get_2048_bit_constants().2,
+        p4096,
+    ];
+
+    for p in &moduli {
+        let base = ZKPUtils::generate_random_below(p);
+        let exponent = rng.gen_biguint(EXPONENT_BITS);
+        println!(
+            "{}-bit p, {} iterations (default window {})",
+            p.bits(),
+            ITERATIONS,
+            FixedBaseTable::window_for_modulus_bits(p.bits())
+        );
+        println!("  modpow:   {:?}/iter", time(|| base.modpow(&exponent, p)));
+        for window in 3..=7 {
+            let start = Instant::now();
+            let table = FixedBaseTable::with_window(&base, p, EXPONENT_BITS, window);
+            let build = start.elapsed();
+            let entries = EXPONENT_BITS.div_ceil(u64::from(window)) * ((1 << window) - 1);
+            println!(
+                "  window {}: {:?}/iter, {} entries built in {:?}",
+                window,
+                time(|| table.pow(&exponent)),
+                entries,
+                build
+            );
+        }
+    }
+}
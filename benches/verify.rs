@@ -0,0 +1,110 @@
+//! Compares the reduction strategies for the products in `ZKPParameters::verify`
+//! on a 4096-bit modulus, separate `modpow`s against `ZKPUtils::modpow_multi`
+//! on the 2048-bit group, and one-by-one verification against
+//! `Verifier::verify_batch` on a batch with repeated `(y, c)` pairs.
+//!
+//! Run with `cargo bench --bench verify`. The modulus is a random odd 4096-bit
+//! number rather than a safe prime: the cost of `modpow`, multiplication and
+//! reduction does not depend on primality, and the proof does not need to be
+//! valid for the timings to be meaningful.
+
+use chaum_pedersen_auth::{
+    ProofInput, Prover, ResponseConvention, Verifier, ZKPParameters, ZKPProtocol, ZKPSystem,
+    ZKPUtils,
+};
+use num_bigint::{BigUint, RandBigInt};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 20;
+
+fn time<F: FnMut() -> bool>(mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let mut rng = rand::thread_rng();
+    let p = rng.gen_biguint(4096) | BigUint::from(1u32) | (BigUint::from(1u32) << 4095);
+    let q = rng.gen_biguint(256);
+    let params = ZKPParameters {
+        alpha: ZKPUtils::generate_random_below(&p),
+        beta: ZKPUtils::generate_random_below(&p),
+        p,
+        q,
+        convention: ResponseConvention::Subtractive,
+        c_max: None,
+    };
+
+    let y1 = ZKPUtils::generate_random_below(&params.p);
+    let y2 = ZKPUtils::generate_random_below(&params.p);
+    let r1 = ZKPUtils::generate_random_below(&params.p);
+    let r2 = ZKPUtils::generate_random_below(&params.p);
+    let c = ZKPUtils::generate_random_below(&params.q);
+    let s = ZKPUtils::generate_random_below(&params.q);
+
+    let one = BigUint::from(1u32);
+    let modpow_reduction = time(|| {
+        let lhs1 =
+            (params.alpha.modpow(&s, &params.p) * y1.modpow(&c, &params.p)).modpow(&one, &params.p);
+        let lhs2 =
+            (params.beta.modpow(&s, &params.p) * y2.modpow(&c, &params.p)).modpow(&one, &params.p);
+        lhs1 == r1 && lhs2 == r2
+    });
+    let rem_reduction = time(|| {
+        let lhs1 = (params.alpha.modpow(&s, &params.p) * y1.modpow(&c, &params.p)) % &params.p;
+        let lhs2 = (params.beta.modpow(&s, &params.p) * y2.modpow(&c, &params.p)) % &params.p;
+        lhs1 == r1 && lhs2 == r2
+    });
+    let multi_exp = time(|| params.verify((&r1, &r2), &c, &s, (&y1, &y2)));
+
+    println!("verify, 4096-bit p, {} iterations", ITERATIONS);
+    println!("  modpow(1, p) reduction: {:?}/iter", modpow_reduction);
+    println!("  single % reduction:     {:?}/iter", rem_reduction);
+    println!("  modpow_multi (verify):  {:?}/iter", multi_exp);
+
+    let (alpha, _, p, q) = ZKPUtils::get_2048_bit_constants();
+    let y = ZKPUtils::generate_random_below(&p);
+    let c = ZKPUtils::generate_random_below(&q);
+    let s = ZKPUtils::generate_random_below(&q);
+    let separate =
+        time(|| black_box((alpha.modpow(&s, &p) * y.modpow(&c, &p)) % &p) != BigUint::from(0u32));
+    let simultaneous = time(|| {
+        black_box(ZKPUtils::modpow_multi(&[(&alpha, &s), (&y, &c)], &p)) != BigUint::from(0u32)
+    });
+
+    println!("alpha^s * y^c, 2048-bit group, {} iterations", ITERATIONS);
+    println!("  two modpows:  {:?}/iter", separate);
+    println!("  modpow_multi: {:?}/iter", simultaneous);
+
+    let (alpha, beta, p, q) = ZKPUtils::get_2048_bit_constants();
+    let system = ZKPSystem::new(p, q, alpha, beta);
+    let verifier = Verifier::new(&system);
+    let mut proofs = Vec::new();
+    for _ in 0..4 {
+        let prover = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+        let challenge = verifier.generate_challenge();
+        for _ in 0..4 {
+            let (commitments, randomness) = prover.generate_commitments();
+            proofs.push(ProofInput {
+                response: prover.generate_response(&challenge, &randomness),
+                commitments,
+                challenge: challenge.clone(),
+                public_values: prover.public_values_owned(),
+            });
+        }
+    }
+    let one_by_one = time(|| verifier.verify_stream(proofs.iter().cloned()).all(|ok| ok));
+    let batched = time(|| verifier.verify_batch(&proofs).into_iter().all(|ok| ok));
+
+    println!(
+        "{} proofs, 4 per (y, c), 2048-bit group, {} iterations",
+        proofs.len(),
+        ITERATIONS
+    );
+    println!("  one by one:   {:?}/iter", one_by_one);
+    println!("  verify_batch: {:?}/iter", batched);
+}
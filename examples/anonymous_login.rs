@@ -0,0 +1,171 @@
+//! Logs in as one of two registered users without revealing which.
+//!
+//! Run with `cargo run --example anonymous_login`. Set
+//! `ANONYMOUS_LOGIN_DEBUG=1` to also print which branch was simulated; that
+//! is exactly what the proof hides, so it is never printed otherwise.
+//!
+//! The proof is the usual OR-composition of two Chaum-Pedersen proofs. The
+//! prover picks the challenge and response of the branch it cannot answer
+//! first and derives matching commitments, then answers the real branch with
+//! whatever remains of the verifier's challenge. The verifier only checks
+//! that the two branch challenges add up to its own modulo `q` and that both
+//! transcripts verify, which they do whichever branch was real.
+
+use chaum_pedersen_auth::{ResponseConvention, ZKPProtocol, ZKPSystem, ZKPUtils};
+use num_bigint::BigUint;
+
+type Pair = (BigUint, BigUint);
+
+/// One branch of the proof as sent to the verifier.
+struct Branch {
+    commitments: Pair,
+    challenge: BigUint,
+    response: BigUint,
+}
+
+/// The prover's state between the commitments and the response.
+struct OrProver<'a> {
+    system: &'a ZKPSystem,
+    known: usize,
+    secret: BigUint,
+    randomness: BigUint,
+    simulated: (BigUint, BigUint),
+    commitments: [Pair; 2],
+}
+
+impl<'a> OrProver<'a> {
+    /// Commits to both branches, knowing the secret of `members[known]`.
+    fn commit(system: &'a ZKPSystem, members: &[Pair; 2], known: usize, secret: BigUint) -> Self {
+        let q = system.get_order();
+        let randomness = ZKPUtils::generate_random_below(q);
+        let challenge = ZKPUtils::generate_random_below(q);
+        let response = ZKPUtils::generate_random_below(q);
+
+        let mut commitments = [
+            system.compute_commitments(&randomness),
+            simulate(system, &members[1 - known], &challenge, &response),
+        ];
+        if known == 1 {
+            commitments.swap(0, 1);
+        }
+        Self {
+            system,
+            known,
+            secret,
+            randomness,
+            simulated: (challenge, response),
+            commitments,
+        }
+    }
+
+    /// Answers the verifier's `challenge` on both branches.
+    fn respond(self, challenge: &BigUint) -> [Branch; 2] {
+        let q = self.system.get_order();
+        let (simulated_challenge, simulated_response) = self.simulated;
+        let real_challenge = (challenge % q + q - &simulated_challenge) % q;
+        let real_response =
+            self.system
+                .compute_response(&self.randomness, &real_challenge, &self.secret);
+
+        let [first, second] = self.commitments;
+        let mut answers = [
+            (real_challenge, real_response),
+            (simulated_challenge, simulated_response),
+        ];
+        if self.known == 1 {
+            answers.swap(0, 1);
+        }
+        let [(c0, s0), (c1, s1)] = answers;
+        [
+            Branch {
+                commitments: first,
+                challenge: c0,
+                response: s0,
+            },
+            Branch {
+                commitments: second,
+                challenge: c1,
+                response: s1,
+            },
+        ]
+    }
+}
+
+/// Returns commitments under which `(challenge, response)` verifies for
+/// `public_values`, without knowing their secret.
+fn simulate(
+    system: &ZKPSystem,
+    public_values: &Pair,
+    challenge: &BigUint,
+    response: &BigUint,
+) -> Pair {
+    let params = system.parameters();
+    let p = &params.p;
+    // The exponent `verify` applies to the public values.
+    let exponent = match params.convention {
+        ResponseConvention::Subtractive => challenge.clone(),
+        ResponseConvention::Additive => &params.q - challenge,
+    };
+    let (alpha_s, beta_s) = system.compute_commitments(response);
+    (
+        alpha_s * public_values.0.modpow(&exponent, p) % p,
+        beta_s * public_values.1.modpow(&exponent, p) % p,
+    )
+}
+
+/// Accepts if some member of `members` produced `branches` for `challenge`.
+fn verify(
+    system: &ZKPSystem,
+    members: &[Pair; 2],
+    challenge: &BigUint,
+    branches: &[Branch; 2],
+) -> bool {
+    let q = system.get_order();
+    let split = (&branches[0].challenge + &branches[1].challenge) % q;
+    split == challenge % q
+        && members.iter().zip(branches).all(|(member, branch)| {
+            system.verify(
+                (&branch.commitments.0, &branch.commitments.1),
+                &branch.challenge,
+                &branch.response,
+                (&member.0, &member.1),
+            )
+        })
+}
+
+fn main() {
+    let debug = std::env::var_os("ANONYMOUS_LOGIN_DEBUG").is_some_and(|v| v != "0");
+    let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+    let system = ZKPSystem::new(p, q.clone(), alpha, beta);
+
+    // Registration: both users publish their public values.
+    let secrets = [
+        ZKPUtils::generate_random_below(&q),
+        ZKPUtils::generate_random_below(&q),
+    ];
+    let members = [
+        system.compute_public_values(&secrets[0]),
+        system.compute_public_values(&secrets[1]),
+    ];
+    println!("registered 2 users");
+
+    for (known, secret) in secrets.into_iter().enumerate() {
+        let prover = OrProver::commit(&system, &members, known, secret);
+        let challenge = ZKPUtils::generate_random_below(&q);
+        let branches = prover.respond(&challenge);
+        let accepted = verify(&system, &members, &challenge, &branches);
+        println!("login as a member of the set: accepted = {}", accepted);
+        if debug {
+            println!("  (debug) simulated branch {}", 1 - known);
+        }
+        assert!(accepted);
+    }
+
+    // Someone who knows neither secret can simulate only one branch.
+    let outsider = ZKPUtils::generate_random_below(&q);
+    let prover = OrProver::commit(&system, &members, 0, outsider);
+    let challenge = ZKPUtils::generate_random_below(&q);
+    let accepted = verify(&system, &members, &challenge, &prover.respond(&challenge));
+    println!("login with an unregistered secret: accepted = {}", accepted);
+    assert!(!accepted);
+}
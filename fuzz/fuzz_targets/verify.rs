@@ -0,0 +1,25 @@
+#![no_main]
+
+use chaum_pedersen_auth::{Verifier, ZKPSystem};
+use libfuzzer_sys::fuzz_target;
+use num_bigint::BigUint;
+
+// Feeds arbitrary (r1, r2, c, s, y1, y2) into `verify` over a tiny group
+// (p = 23, q = 11, alpha = 4, beta = 9). The only property checked is that
+// verification never panics, whatever the sizes of the inputs.
+fuzz_target!(|data: &[u8]| {
+    let system = ZKPSystem::new(
+        BigUint::from(23u32),
+        BigUint::from(11u32),
+        BigUint::from(4u32),
+        BigUint::from(9u32),
+    );
+    let verifier = Verifier::new(&system);
+
+    let chunk = data.len().div_ceil(6).max(1);
+    let mut values = data.chunks(chunk).map(BigUint::from_bytes_be);
+    let mut next = || values.next().unwrap_or_default();
+    let (r1, r2, c, s, y1, y2) = (next(), next(), next(), next(), next(), next());
+
+    let _: bool = verifier.verify((&r1, &r2), &c, &s, (&y1, &y2));
+});
@@ -1,32 +1,184 @@
+use crate::challenge::{ChallengeSource, RandomChallenges};
+use crate::error::{ProverError, ZKPError};
 use crate::protocol::ZKPProtocol;
+use crate::replay::ReplayCache;
+use crate::secret::SecretProvider;
+use crate::transcript::Transcript;
 use crate::utils::ZKPUtils;
 use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::ops::Deref;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// A non-interactive Chaum-Pedersen proof obtained via Fiat-Shamir.
+///
+/// The challenge is not stored; the verifier recomputes it from the public
+/// values and commitments using the same digest as the prover.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonInteractiveProof {
+    /// The commitments (r1, r2).
+    pub commitments: (BigUint, BigUint),
+    /// The response `s`.
+    pub response: BigUint,
+}
+
+impl NonInteractiveProof {
+    /// The serialization format version written by `to_bytes`.
+    ///
+    /// Version 2 binds the challenge to the group's `hash_domain`; version 1
+    /// proofs would no longer verify and are rejected when decoding.
+    pub const VERSION: u8 = 2;
+
+    /// Serializes the proof.
+    ///
+    /// The encoding is a version byte followed by `r1`, `r2` and `s`, each as
+    /// a 4-byte big-endian length and the big-endian bytes of the value.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        ZKPUtils::encode_versioned(
+            Self::VERSION,
+            &[&self.commitments.0, &self.commitments.1, &self.response],
+        )
+    }
+
+    /// Parses a proof produced by `to_bytes`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(NonInteractiveProof)` if the encoding is a well-formed proof of the current
+    ///   `VERSION`.
+    /// * `Err(ZKPError::UnsupportedProofVersion)` if the version byte is unknown.
+    /// * `Err(ZKPError::MalformedProof)` if the encoding is truncated or has trailing bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ZKPError> {
+        let [r1, r2, response] = ZKPUtils::decode_versioned(bytes, Self::VERSION)?;
+        Ok(Self {
+            commitments: (r1, r2),
+            response,
+        })
+    }
+}
+
+/// A Schnorr-style signature: a non-interactive proof whose challenge also
+/// binds a message.
+///
+/// Produced by `Prover::sign` and checked with `Verifier::verify_signature`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    /// The commitments (r1, r2).
+    pub commitments: (BigUint, BigUint),
+    /// The response `s`.
+    pub response: BigUint,
+}
+
+/// One interactive transcript to check with `Verifier::verify_stream`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofInput {
+    /// The commitments (r1, r2).
+    pub commitments: (BigUint, BigUint),
+    /// The challenge `c`.
+    pub challenge: BigUint,
+    /// The response `s`.
+    pub response: BigUint,
+    /// The public values (y1, y2) of the prover.
+    pub public_values: (BigUint, BigUint),
+}
+
+/// The details of one verification, from `Verifier::verify_detailed`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifyOutcome {
+    /// Whether the proof was accepted, as `verify` would report.
+    pub accepted: bool,
+    /// Whether `r1 == alpha^s * y1^c` held.
+    pub first_check: bool,
+    /// Whether `r2 == beta^s * y2^c` held.
+    pub second_check: bool,
+    /// Time spent verifying.
+    pub elapsed: Duration,
+}
+
+/// A ZKP system either borrowed from the caller or owned by the actor.
+enum SystemRef<'a, T> {
+    Borrowed(&'a T),
+    Owned(T),
+}
+
+impl<T> Deref for SystemRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            SystemRef::Borrowed(system) => system,
+            SystemRef::Owned(system) => system,
+        }
+    }
+}
 
 /// Represents the Prover in the ZKP protocol.
 ///
 /// The Prover holds the secret and interacts with the ZKP system to generate proofs.
-pub struct Prover<'a, T: ZKPProtocol> {
-    system: &'a T,
-    secret: BigUint,
-    public_values: (BigUint, BigUint),
+/// The secret is accessed through a `SecretProvider`; by default it is a plain
+/// in-memory `BigUint`. The system is borrowed (`new`) or owned (`new_owned`).
+///
+/// The public values are computed on first use, so a prover that only
+/// answers challenges for already registered values never pays for them.
+pub struct Prover<'a, T: ZKPProtocol, S: SecretProvider = BigUint> {
+    system: SystemRef<'a, T>,
+    secret: S,
+    public_values: OnceLock<(BigUint, BigUint)>,
 }
 
-impl<'a, T: ZKPProtocol> Prover<'a, T> {
+impl<'a, T: ZKPProtocol, S: SecretProvider> Prover<'a, T, S> {
     /// Creates a new `Prover`.
     ///
     /// # Arguments
     ///
     /// * `system` - The ZKP system to use.
-    /// * `secret` - The secret value `x` to be proven.
-    pub fn new(system: &'a T, secret: BigUint) -> Self {
-        let public_values = system.compute_public_values(&secret);
+    /// * `secret` - The secret value `x` to be proven, or a provider holding it.
+    ///
+    /// A `BigUint` secret is reduced modulo `q` and stored in that form, which
+    /// keeps `c * x` small in the response. The public values are computed
+    /// lazily from the reduced secret; they equal those of the original only because
+    /// `alpha` and `beta` have order `q`, so a system with generators of any
+    /// other order would register different values than `alpha^x` suggests.
+    pub fn new(system: &'a T, secret: S) -> Self {
         Self {
-            system,
+            system: SystemRef::Borrowed(system),
+            secret: secret.reduce(system),
+            public_values: OnceLock::new(),
+        }
+    }
+
+    /// Creates a new `Prover` that owns its system.
+    ///
+    /// Unlike `new`, the prover does not borrow anything, so it can be stored
+    /// in a struct or returned from a function independently of the system.
+    ///
+    /// # Arguments
+    ///
+    /// * `system` - The ZKP system to use.
+    /// * `secret` - The secret value `x` to be proven, or a provider holding it.
+    ///
+    /// The secret is reduced as in `new`.
+    pub fn new_owned(system: T, secret: S) -> Self {
+        let secret = secret.reduce(&system);
+        Self {
+            system: SystemRef::Owned(system),
             secret,
-            public_values,
+            public_values: OnceLock::new(),
         }
     }
 
+    /// Replaces the secret, e.g. for key rotation, and drops the cached
+    /// public values so they are recomputed for the new secret.
+    ///
+    /// The new secret is reduced as in `new`, and the old one is dropped.
+    /// Proofs made afterwards verify only against the new `public_values`.
+    pub fn change_secret(&mut self, new_secret: S) {
+        self.secret = new_secret.reduce(&*self.system);
+        self.public_values = OnceLock::new();
+    }
+
     /// Generates the commitments for the proof.
     ///
     /// This is the first step of the protocol.
@@ -42,10 +194,23 @@ impl<'a, T: ZKPProtocol> Prover<'a, T> {
         (commitments, randomness)
     }
 
+    /// Generates `n` independent commitments, e.g. for multi-round or
+    /// cut-and-choose protocols.
+    ///
+    /// # Returns
+    ///
+    /// `n` pairs `((r1, r2), k)`, each with its own fresh randomness `k`.
+    pub fn batch_commit(&self, n: usize) -> Vec<((BigUint, BigUint), BigUint)> {
+        (0..n).map(|_| self.generate_commitments()).collect()
+    }
+
     /// Generates the response to the challenge.
     ///
     /// This is the third step of the Sigma protocol.
-    /// It computes `s = k - c * x mod q`.
+    /// It computes `s = k - c * x mod q` under
+    /// `ResponseConvention::Subtractive` or `s = k + c * x mod q` under
+    /// `ResponseConvention::Additive`, as selected by the system's
+    /// `ZKPParameters::convention`.
     ///
     /// # Arguments
     ///
@@ -56,21 +221,176 @@ impl<'a, T: ZKPProtocol> Prover<'a, T> {
     ///
     /// The response value `s`.
     pub fn generate_response(&self, challenge: &BigUint, randomness: &BigUint) -> BigUint {
-        self.system
-            .compute_response(randomness, challenge, &self.secret)
+        self.secret.respond(&*self.system, randomness, challenge)
+    }
+
+    /// Generates the response to a challenge received as big-endian bytes.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(s)` if the challenge is below `q`.
+    /// * `Err(ZKPError::ChallengeOutOfRange)` otherwise.
+    pub fn generate_response_bytes(
+        &self,
+        challenge: &[u8],
+        randomness: &BigUint,
+    ) -> Result<BigUint, ZKPError> {
+        let challenge = parse_challenge(&*self.system, challenge)?;
+        Ok(self.generate_response(&challenge, randomness))
+    }
+
+    /// Generates the response and checks it before it is sent.
+    ///
+    /// Verifies the resulting transcript against the Prover's own public
+    /// values, catching a randomness that does not match the commitments or an
+    /// inconsistent response computation.
+    ///
+    /// # Arguments
+    ///
+    /// * `commitments` - The commitments (r1, r2) sent to the verifier.
+    /// * `challenge` - The challenge `c` received from the verifier.
+    /// * `randomness` - The random value `k` used in the commitment step.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(s)` if the transcript verifies.
+    /// * `Err(ProverError::SelfVerificationFailed)` otherwise.
+    pub fn generate_response_checked(
+        &self,
+        commitments: (&BigUint, &BigUint),
+        challenge: &BigUint,
+        randomness: &BigUint,
+    ) -> Result<BigUint, ProverError> {
+        let response = self.generate_response(challenge, randomness);
+        let (y1, y2) = self.public_values();
+        if self
+            .system
+            .verify(commitments, challenge, &response, (y1, y2))
+        {
+            Ok(response)
+        } else {
+            Err(ProverError::SelfVerificationFailed)
+        }
+    }
+
+    /// Produces a non-interactive proof, deriving the challenge with SHA-256.
+    ///
+    /// Equivalent to `prove_non_interactive_with::<Sha256>()`.
+    pub fn prove_non_interactive(&self) -> NonInteractiveProof {
+        self.prove_non_interactive_with::<Sha256>()
+    }
+
+    /// Produces a non-interactive proof, deriving the challenge with digest `D`.
+    ///
+    /// The challenge is `c = H(domain, y1, y2, r1, r2) mod q` (or mod `c_max`
+    /// when the system sets one), where `domain`
+    /// is the system's `hash_domain`. The verifier must use the same digest in
+    /// `Verifier::verify_non_interactive_with`.
+    pub fn prove_non_interactive_with<D: Digest>(&self) -> NonInteractiveProof {
+        let (commitments, randomness) = self.generate_commitments();
+        let (y1, y2) = self.public_values();
+        let challenge = ZKPUtils::fiat_shamir_challenge_in_domain::<D>(
+            self.system.challenge_bound(),
+            &self.system.hash_domain(),
+            &[y1, y2, &commitments.0, &commitments.1],
+        );
+        let response = self.generate_response(&challenge, &randomness);
+        NonInteractiveProof {
+            commitments,
+            response,
+        }
+    }
+
+    /// Signs `message` with the secret.
+    ///
+    /// The challenge is `c = SHA-256(message, r1, r2, y1, y2, domain) mod q`,
+    /// every field length-prefixed, where `domain` is the system's
+    /// `hash_domain`.
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        let (commitments, randomness) = self.generate_commitments();
+        let (y1, y2) = self.public_values();
+        let challenge = signature_challenge(
+            &*self.system,
+            message,
+            (&commitments.0, &commitments.1),
+            (y1, y2),
+        );
+        let response = self.generate_response(&challenge, &randomness);
+        Signature {
+            commitments,
+            response,
+        }
+    }
+
+    /// Checks that a revealed challenge matches the verifier's earlier commitment.
+    ///
+    /// Used in the commit-reveal variant of the protocol, where the verifier
+    /// sends `H(c)` before the prover commits, so it cannot pick `c` adaptively.
+    ///
+    /// # Arguments
+    ///
+    /// * `commitment` - The hash `H(c)` received before sending the commitments.
+    /// * `challenge` - The challenge `c` revealed by the verifier.
+    ///
+    /// # Returns
+    ///
+    /// `true` if `H(challenge) == commitment`, `false` otherwise.
+    pub fn verify_challenge_commitment(&self, commitment: &[u8; 32], challenge: &BigUint) -> bool {
+        ZKPUtils::hash_challenge(challenge) == *commitment
     }
 
     /// Returns the public keys associated with the Prover's secret.
+    ///
+    /// Computed on the first call and cached.
     pub fn public_values(&self) -> &(BigUint, BigUint) {
-        &self.public_values
+        self.public_values
+            .get_or_init(|| self.secret.public_values(&*self.system))
+    }
+
+    /// Returns an owned copy of the public keys `(y1, y2)`.
+    ///
+    /// Convenient when the values need to outlive the `Prover`, e.g. when
+    /// building a registration request.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use chaum_pedersen_auth::{Prover, ZKPSystem, ZKPUtils};
+    ///
+    /// let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+    /// let system = ZKPSystem::new(p, q, alpha, beta);
+    /// let secret = ZKPUtils::generate_random_below(&system.parameters().q);
+    /// let prover = Prover::new(&system, secret);
+    ///
+    /// let (y1, y2) = prover.public_values_owned();
+    /// assert_eq!((&y1, &y2), (&prover.public_values().0, &prover.public_values().1));
+    /// ```
+    pub fn public_values_owned(&self) -> (BigUint, BigUint) {
+        self.public_values().clone()
+    }
+}
+
+/// Prints the public values only; the secret is always shown as `<redacted>`.
+///
+/// Public values not yet computed show as `None`: formatting a prover never
+/// computes them.
+impl<T: ZKPProtocol, S: SecretProvider> fmt::Debug for Prover<'_, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Prover")
+            .field("secret", &format_args!("<redacted>"))
+            .field("public_values", &self.public_values.get())
+            .finish()
     }
 }
 
 /// Represents the Verifier in the ZKP protocol.
 ///
 /// The Verifier challenges the Prover and verifies the proof.
+/// The system is borrowed (`new`) or owned (`new_owned`).
 pub struct Verifier<'a, T: ZKPProtocol> {
-    system: &'a T,
+    system: SystemRef<'a, T>,
+    subgroup_checks: bool,
+    challenges: Box<dyn ChallengeSource + 'a>,
 }
 
 impl<'a, T: ZKPProtocol> Verifier<'a, T> {
@@ -80,22 +400,306 @@ impl<'a, T: ZKPProtocol> Verifier<'a, T> {
     ///
     /// * `system` - The ZKP system (or protocol implementation) to use.
     pub fn new(system: &'a T) -> Self {
-        Self { system }
+        Self {
+            system: SystemRef::Borrowed(system),
+            subgroup_checks: true,
+            challenges: Box::new(RandomChallenges),
+        }
+    }
+
+    /// Creates a new `Verifier` that owns its system.
+    ///
+    /// # Arguments
+    ///
+    /// * `system` - The ZKP system (or protocol implementation) to use.
+    pub fn new_owned(system: T) -> Self {
+        Self {
+            system: SystemRef::Owned(system),
+            subgroup_checks: true,
+            challenges: Box::new(RandomChallenges),
+        }
+    }
+
+    /// Sets whether `verify` and `verify_batch` check that the commitments
+    /// and public values are group elements before checking the equations.
+    ///
+    /// On by default. Turn it off only when every value has already been
+    /// validated, e.g. public values checked once at registration.
+    pub fn with_subgroup_checks(mut self, enabled: bool) -> Self {
+        self.subgroup_checks = enabled;
+        self
+    }
+
+    /// Draws challenges from `source` instead of the local CSPRNG.
+    pub fn with_challenge_source(mut self, source: impl ChallengeSource + 'a) -> Self {
+        self.challenges = Box::new(source);
+        self
     }
 
     /// Generates a random challenge for the Prover.
     ///
-    /// This is the second step of theprotocol.
+    /// This is the second step of theprotocol. The challenge comes from the
+    /// verifier's `ChallengeSource`.
     ///
     /// # Returns
     ///
     /// A random challenge value `c`.
     pub fn generate_challenge(&self) -> BigUint {
-        ZKPUtils::generate_random_below(self.system.get_order())
+        self.challenges
+            .next_challenge(self.system.challenge_bound())
+    }
+
+    /// Challenges with fewer bits of entropy than this are guessable enough
+    /// that a cheating prover can hope to hit one in practice.
+    pub const MIN_CHALLENGE_ENTROPY_BITS: u64 = 128;
+
+    /// Returns the whole bits of entropy in a challenge from
+    /// `generate_challenge`, i.e. `floor(log2(bound))` for the system's
+    /// `challenge_bound` (`q` unless a shorter challenge space is set).
+    ///
+    /// A value below `MIN_CHALLENGE_ENTROPY_BITS` means the group is a toy
+    /// group and a prover without the secret can guess the challenge.
+    pub fn challenge_entropy_bits(&self) -> u64 {
+        self.system.challenge_bound().bits().saturating_sub(1)
+    }
+
+    /// Generates a random challenge together with a commitment to it.
+    ///
+    /// The commitment `H(c)` is sent to the Prover before it commits; `c` is
+    /// revealed afterwards and checked with `Prover::verify_challenge_commitment`.
+    ///
+    /// # Returns
+    ///
+    /// A tuple `(H(c), c)`.
+    pub fn commit_challenge(&self) -> ([u8; 32], BigUint) {
+        let challenge = self.generate_challenge();
+        (ZKPUtils::hash_challenge(&challenge), challenge)
+    }
+
+    /// Verifies a non-interactive proof whose challenge was derived with SHA-256.
+    pub fn verify_non_interactive(
+        &self,
+        proof: &NonInteractiveProof,
+        public_values: (&BigUint, &BigUint),
+    ) -> bool {
+        self.verify_non_interactive_with::<Sha256>(proof, public_values)
+    }
+
+    /// Verifies a non-interactive proof whose challenge was derived with digest `D`.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The proof produced by `Prover::prove_non_interactive_with::<D>`.
+    /// * `public_values` - The public values (y1, y2) claimed by the Prover.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the proof is valid, `false` otherwise.
+    pub fn verify_non_interactive_with<D: Digest>(
+        &self,
+        proof: &NonInteractiveProof,
+        public_values: (&BigUint, &BigUint),
+    ) -> bool {
+        let (r1, r2) = &proof.commitments;
+        let challenge = self.non_interactive_challenge_with::<D>(proof, public_values);
+        self.verify((r1, r2), &challenge, &proof.response, public_values)
+    }
+
+    /// Returns the Fiat-Shamir challenge `c` a non-interactive proof answers.
+    ///
+    /// `verify_non_interactive_with::<D>` is `verify` with this challenge, so
+    /// the two paths can be cross-checked.
+    pub fn non_interactive_challenge_with<D: Digest>(
+        &self,
+        proof: &NonInteractiveProof,
+        public_values: (&BigUint, &BigUint),
+    ) -> BigUint {
+        let (r1, r2) = &proof.commitments;
+        ZKPUtils::fiat_shamir_challenge_in_domain::<D>(
+            self.system.challenge_bound(),
+            &self.system.hash_domain(),
+            &[public_values.0, public_values.1, r1, r2],
+        )
+    }
+
+    /// Verifies a signature produced by `Prover::sign`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if `signature` is a valid signature of `message` under the
+    /// public values (y1, y2), `false` otherwise.
+    pub fn verify_signature(
+        &self,
+        message: &[u8],
+        signature: &Signature,
+        public_values: (&BigUint, &BigUint),
+    ) -> bool {
+        let (r1, r2) = &signature.commitments;
+        let challenge = signature_challenge(&*self.system, message, (r1, r2), public_values);
+        self.verify((r1, r2), &challenge, &signature.response, public_values)
+    }
+
+    /// Verifies several `(challenge, response)` pairs against one commitment.
+    ///
+    /// # Arguments
+    ///
+    /// * `commitments` - The commitments (r1, r2) from the Prover.
+    /// * `public_values` - The public values (y1, y2) claimed by the Prover.
+    /// * `answers` - The `(c, s)` pairs to check.
+    ///
+    /// # Returns
+    ///
+    /// One result per pair, in order: `true` if that pair verifies.
+    pub fn verify_many_challenges(
+        &self,
+        commitments: (&BigUint, &BigUint),
+        public_values: (&BigUint, &BigUint),
+        answers: &[(BigUint, BigUint)],
+    ) -> Vec<bool> {
+        answers
+            .iter()
+            .map(|(challenge, response)| {
+                self.verify(commitments, challenge, response, public_values)
+            })
+            .collect()
+    }
+
+    /// Verifies proofs lazily, one per item pulled from the returned iterator.
+    ///
+    /// Nothing is verified up front, so the caller can stop early (e.g. at the
+    /// first `false`) without the remaining proofs being read or checked.
+    ///
+    /// # Arguments
+    ///
+    /// * `proofs` - The transcripts to check, in order.
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding `true` for each proof that verifies.
+    pub fn verify_stream<'s, I>(&'s self, proofs: I) -> impl Iterator<Item = bool> + 's
+    where
+        I: IntoIterator<Item = ProofInput>,
+        I::IntoIter: 's,
+    {
+        proofs.into_iter().map(move |proof| {
+            let (r1, r2) = &proof.commitments;
+            let (y1, y2) = &proof.public_values;
+            self.verify((r1, r2), &proof.challenge, &proof.response, (y1, y2))
+        })
+    }
+
+    /// Verifies a proof, rejecting exact replays of recently accepted ones.
+    ///
+    /// The `(r1, r2, s)` fingerprint is looked up in `cache` first; a hit is
+    /// rejected without doing the verification math. Valid proofs are then
+    /// recorded so that a second submission is rejected.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the proof is valid and has not been seen, `false` otherwise.
+    pub fn verify_with_replay_protection(
+        &self,
+        cache: &mut ReplayCache,
+        commitments: (&BigUint, &BigUint),
+        challenge: &BigUint,
+        response: &BigUint,
+        public_values: (&BigUint, &BigUint),
+    ) -> bool {
+        let fingerprint = ReplayCache::fingerprint(commitments, response);
+        if cache.contains(&fingerprint) {
+            return false;
+        }
+        let is_valid = self.verify(commitments, challenge, response, public_values);
+        if is_valid {
+            cache.insert(fingerprint);
+        }
+        is_valid
+    }
+
+    /// Verifies a batch of proofs with the system's `verify_batch`.
+    ///
+    /// # Returns
+    ///
+    /// One result per proof, in order: `true` if that proof verifies.
+    pub fn verify_batch(&self, proofs: &[ProofInput]) -> Vec<bool> {
+        self.system
+            .verify_batch(proofs)
+            .into_iter()
+            .zip(proofs)
+            .map(|(is_valid, proof)| {
+                let (r1, r2) = &proof.commitments;
+                let (y1, y2) = &proof.public_values;
+                is_valid && self.members((r1, r2), (y1, y2))
+            })
+            .collect()
+    }
+
+    /// Verifies a proof against several candidate public values, such as the
+    /// old and new values during a secret migration.
+    ///
+    /// Every candidate is checked, even after a match, so the running time
+    /// does not reveal which one matched.
+    ///
+    /// # Returns
+    ///
+    /// The index of the first candidate the proof verifies against, or `None`.
+    pub fn verify_any(
+        &self,
+        commitments: (&BigUint, &BigUint),
+        challenge: &BigUint,
+        response: &BigUint,
+        candidates: &[(BigUint, BigUint)],
+    ) -> Option<usize> {
+        candidates
+            .iter()
+            .enumerate()
+            .fold(None, |found, (i, (y1, y2))| {
+                let is_valid = self.verify(commitments, challenge, response, (y1, y2));
+                found.or(is_valid.then_some(i))
+            })
+    }
+
+    /// Verifies a proof whose challenge is given as big-endian bytes.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(valid)` with the outcome of `verify`.
+    /// * `Err(ZKPError::ChallengeOutOfRange)` if the challenge is not below `q`.
+    pub fn verify_bytes(
+        &self,
+        commitments: (&BigUint, &BigUint),
+        challenge: &[u8],
+        response: &BigUint,
+        public_values: (&BigUint, &BigUint),
+    ) -> Result<bool, ZKPError> {
+        let challenge = parse_challenge(&*self.system, challenge)?;
+        Ok(self.verify(commitments, &challenge, response, public_values))
+    }
+
+    /// Verifies a complete protocol run.
+    ///
+    /// This is the recommended entry point; it checks the transcript exactly
+    /// as `verify` checks its parts.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the proof is valid, `false` otherwise.
+    pub fn verify_proof(&self, proof: &Transcript) -> bool {
+        let (r1, r2) = &proof.commitments;
+        let (y1, y2) = &proof.public_values;
+        self.verify((r1, r2), &proof.challenge, &proof.response, (y1, y2))
     }
 
     /// Verifies the proof provided by the Prover.
     ///
+    /// This is the lower-level form of `verify_proof`, for callers that hold
+    /// the values separately.
+    ///
+    /// With subgroup checks on (the default), values that are not elements of
+    /// this verifier's group are rejected before the equations are checked,
+    /// so a proof made under another group, e.g. by a misconfigured client,
+    /// fails cleanly.
+    ///
     /// # Arguments
     ///
     /// * `commitments` - The commitments (r1, r2) from the Prover.
@@ -113,7 +717,88 @@ impl<'a, T: ZKPProtocol> Verifier<'a, T> {
         response: &BigUint,
         public_values: (&BigUint, &BigUint),
     ) -> bool {
-        self.system
-            .verify(commitments, challenge, response, public_values)
+        self.members(commitments, public_values)
+            && self
+                .system
+                .verify(commitments, challenge, response, public_values)
+    }
+
+    /// Verifies a proof like `verify`, reporting each equation and the time
+    /// taken.
+    ///
+    /// Both equations are always evaluated, so `accepted` can be `false`
+    /// with both checks `true` when a value fails the subgroup check.
+    pub fn verify_detailed(
+        &self,
+        commitments: (&BigUint, &BigUint),
+        challenge: &BigUint,
+        response: &BigUint,
+        public_values: (&BigUint, &BigUint),
+    ) -> VerifyOutcome {
+        let started = Instant::now();
+        let (first_check, second_check) =
+            self.system
+                .verify_equations(commitments, challenge, response, public_values);
+        let accepted = first_check && second_check && self.members(commitments, public_values);
+        VerifyOutcome {
+            accepted,
+            first_check,
+            second_check,
+            elapsed: started.elapsed(),
+        }
+    }
+
+    /// Returns `true` if subgroup checks are off or every value is a group
+    /// element.
+    fn members(
+        &self,
+        commitments: (&BigUint, &BigUint),
+        public_values: (&BigUint, &BigUint),
+    ) -> bool {
+        !self.subgroup_checks
+            || [
+                commitments.0,
+                commitments.1,
+                public_values.0,
+                public_values.1,
+            ]
+            .into_iter()
+            .all(|value| self.system.is_group_element(value))
+    }
+}
+
+/// Decodes a big-endian challenge and checks it is below the challenge bound.
+fn parse_challenge<T: ZKPProtocol>(system: &T, bytes: &[u8]) -> Result<BigUint, ZKPError> {
+    let challenge = BigUint::from_bytes_be(bytes);
+    if challenge >= *system.challenge_bound() {
+        return Err(ZKPError::ChallengeOutOfRange);
+    }
+    Ok(challenge)
+}
+
+/// Derives the challenge of a `Signature` over `message`.
+fn signature_challenge<T: ZKPProtocol>(
+    system: &T,
+    message: &[u8],
+    commitments: (&BigUint, &BigUint),
+    public_values: (&BigUint, &BigUint),
+) -> BigUint {
+    let mut hasher = Sha256::new();
+    let values = [
+        commitments.0,
+        commitments.1,
+        public_values.0,
+        public_values.1,
+    ]
+    .map(BigUint::to_bytes_be);
+    let domain = system.hash_domain();
+    for field in [message]
+        .into_iter()
+        .chain(values.iter().map(Vec::as_slice))
+        .chain([domain.as_slice()])
+    {
+        hasher.update((field.len() as u32).to_be_bytes());
+        hasher.update(field);
     }
+    BigUint::from_bytes_be(&hasher.finalize()) % system.challenge_bound()
 }
@@ -0,0 +1,78 @@
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+use std::time::SystemTime;
+
+/// One authentication decision reported to an `AuthAuditor`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEvent {
+    /// The user the decision is about.
+    pub user: String,
+    /// The id of the challenge, for challenge and verification events.
+    pub auth_id: Option<String>,
+    /// When the decision was made.
+    pub timestamp: SystemTime,
+    /// SHA-256 over the transcript values known at that point; see
+    /// `AuditEvent::fingerprint`.
+    pub fingerprint: [u8; 32],
+}
+
+impl AuditEvent {
+    /// Creates an event stamped with the current time.
+    pub fn new(user: &str, auth_id: Option<&str>, values: &[&BigUint]) -> Self {
+        Self {
+            user: user.to_string(),
+            auth_id: auth_id.map(str::to_string),
+            timestamp: SystemTime::now(),
+            fingerprint: Self::fingerprint(values),
+        }
+    }
+
+    /// Hashes `values`, each as a 4-byte big-endian length followed by its
+    /// big-endian bytes.
+    ///
+    /// The service passes `y1, y2` for a registration, adds `r1, r2, c` for a
+    /// challenge and `s` for a verification, so the fingerprints of one run
+    /// can be matched against a stored `Transcript`.
+    pub fn fingerprint(values: &[&BigUint]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        for value in values {
+            let bytes = value.to_bytes_be();
+            hasher.update((bytes.len() as u32).to_be_bytes());
+            hasher.update(&bytes);
+        }
+        hasher.finalize().into()
+    }
+}
+
+/// Receives every authentication decision of an `AuthService`, e.g. to keep
+/// a compliance record.
+///
+/// Every method defaults to doing nothing. Methods are called on the request
+/// path after the decision is made, and should not block.
+pub trait AuthAuditor: Send + Sync {
+    /// A user was registered.
+    fn on_register(&self, event: &AuditEvent) {
+        let _ = event;
+    }
+
+    /// A challenge was issued.
+    fn on_challenge(&self, event: &AuditEvent) {
+        let _ = event;
+    }
+
+    /// A response to a pending challenge was accepted.
+    fn on_verify_success(&self, event: &AuditEvent) {
+        let _ = event;
+    }
+
+    /// A response to a pending challenge was rejected.
+    fn on_verify_failure(&self, event: &AuditEvent) {
+        let _ = event;
+    }
+}
+
+/// An auditor that discards every event.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopAuditor;
+
+impl AuthAuditor for NoopAuditor {}
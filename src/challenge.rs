@@ -0,0 +1,23 @@
+use crate::utils::ZKPUtils;
+use num_bigint::BigUint;
+
+/// Supplies the challenges a `Verifier` sends to provers.
+///
+/// The default, `RandomChallenges`, draws from the local CSPRNG. Deployments
+/// that take challenges from elsewhere, such as a public randomness beacon,
+/// implement this instead. Challenges must be unpredictable to the prover
+/// for the protocol to stay sound.
+pub trait ChallengeSource: Send + Sync {
+    /// Returns the next challenge, which must be below `q`.
+    fn next_challenge(&self, q: &BigUint) -> BigUint;
+}
+
+/// Draws challenges uniformly below `q` from the thread-local CSPRNG.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RandomChallenges;
+
+impl ChallengeSource for RandomChallenges {
+    fn next_challenge(&self, q: &BigUint) -> BigUint {
+        ZKPUtils::generate_random_below(q)
+    }
+}
@@ -0,0 +1,105 @@
+use crate::actors::{ProofInput, Verifier};
+use crate::protocol::ZKPProtocol;
+use crate::utils::ZKPUtils;
+use num_bigint::BigUint;
+
+/// Runs the checks of `assert_zkp_protocol_conformance!` against `system`,
+/// panicking on the first violation.
+///
+/// * An honest proof verifies, through `verify`, `verify_equations` and
+///   `verify_batch`.
+/// * Proofs with the response, challenge or a commitment tampered with are
+///   rejected.
+/// * Public values and commitments are group elements, `non_member` is not,
+///   and a `Verifier` rejects a proof carrying it.
+///
+/// `non_member` defaults to 0, which is not an element of `Z_p*`.
+#[track_caller]
+pub fn check_conformance<T: ZKPProtocol>(system: &T, non_member: Option<&BigUint>) {
+    let q = system.get_order();
+    let one = BigUint::from(1u32);
+    let secret = ZKPUtils::generate_random_in_range(&one, q);
+    let nonce = ZKPUtils::generate_random_in_range(&one, q);
+    let challenge = ZKPUtils::generate_random_in_range(&one, q);
+
+    let (y1, y2) = system.compute_public_values(&secret);
+    let (r1, r2) = system.compute_commitments(&nonce);
+    let response = system.compute_response(&nonce, &challenge, &secret);
+
+    assert!(
+        system.verify((&r1, &r2), &challenge, &response, (&y1, &y2)),
+        "an honest proof must verify"
+    );
+    assert_eq!(
+        system.verify_equations((&r1, &r2), &challenge, &response, (&y1, &y2)),
+        (true, true),
+        "both equations of an honest proof must hold"
+    );
+    let honest = ProofInput {
+        commitments: (r1.clone(), r2.clone()),
+        challenge: challenge.clone(),
+        response: response.clone(),
+        public_values: (y1.clone(), y2.clone()),
+    };
+    assert_eq!(
+        system.verify_batch(std::slice::from_ref(&honest)),
+        [true],
+        "verify_batch must accept an honest proof"
+    );
+
+    let tampered_response = (&response + 1u32) % q;
+    assert!(
+        !system.verify((&r1, &r2), &challenge, &tampered_response, (&y1, &y2)),
+        "a tampered response must be rejected"
+    );
+    let tampered_challenge = (&challenge + 1u32) % q;
+    assert!(
+        !system.verify((&r1, &r2), &tampered_challenge, &response, (&y1, &y2)),
+        "a tampered challenge must be rejected"
+    );
+    assert!(
+        !system.verify((&r2, &r1), &challenge, &response, (&y1, &y2)),
+        "swapped commitments must be rejected"
+    );
+
+    for value in [&y1, &y2, &r1, &r2] {
+        assert!(
+            system.is_group_element(value),
+            "public values and commitments must be group elements"
+        );
+    }
+    let zero = BigUint::from(0u32);
+    let non_member = non_member.unwrap_or(&zero);
+    assert!(
+        !system.is_group_element(non_member),
+        "the non-member must not be a group element"
+    );
+    let verifier = Verifier::new(system);
+    assert!(
+        verifier.verify((&r1, &r2), &challenge, &response, (&y1, &y2)),
+        "a verifier must accept an honest proof"
+    );
+    assert!(
+        !verifier.verify((non_member, &r2), &challenge, &response, (&y1, &y2)),
+        "a verifier must reject a commitment outside the group"
+    );
+    assert!(
+        !verifier.verify((&r1, &r2), &challenge, &response, (non_member, &y2)),
+        "a verifier must reject a public value outside the group"
+    );
+}
+
+/// Asserts that a `ZKPProtocol` implementation satisfies the protocol's
+/// contract; see `conformance::check_conformance` for the checks.
+///
+/// Takes the system and, optionally, a value that is not a group element
+/// (0 by default).
+#[macro_export]
+macro_rules! assert_zkp_protocol_conformance {
+    ($system:expr) => {
+        $crate::conformance::check_conformance(&$system, None)
+    };
+    ($system:expr, $non_member:expr) => {
+        $crate::conformance::check_conformance(&$system, Some(&$non_member))
+    };
+}
@@ -0,0 +1,224 @@
+use std::fmt;
+
+/// Errors raised when validating protocol inputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZKPError {
+    /// The value is not in the range `[1, p)`.
+    ElementOutOfRange,
+    /// The value is in range but not in the order-`q` subgroup.
+    ElementNotInSubgroup,
+    /// A serialized proof carries a version this build does not understand.
+    UnsupportedProofVersion(u8),
+    /// A serialized proof is truncated or has trailing bytes.
+    MalformedProof,
+    /// Serialized parameters carry a version this build does not understand.
+    UnsupportedParametersVersion(u8),
+    /// Serialized parameters have an unknown convention or `c_max` tag.
+    MalformedParameters,
+    /// The prover's own response does not verify against its commitments.
+    SelfVerificationFailed,
+    /// A group with this identifier is already registered.
+    DuplicateGroupId(String),
+    /// A group parameter was negative.
+    NegativeParameter(&'static str),
+    /// No user is registered under the given name.
+    UserNotFound,
+    /// No pending challenge exists for the given auth id.
+    SessionNotFound,
+    /// The proof did not verify.
+    AuthenticationFailed,
+    /// Registration without a proof of possession was refused.
+    ProofOfPossessionRequired,
+    /// The proof of possession sent with a registration did not verify.
+    InvalidProofOfPossession,
+    /// A sealed secret could not be opened: wrong passphrase or corrupted data.
+    SecretUnsealFailed,
+    /// A challenge is not below the group order `q`.
+    ChallengeOutOfRange,
+    /// A number was not in its minimal big-endian encoding, or was too wide.
+    NonCanonicalEncoding,
+    /// The group order `q` does not divide `p - 1`.
+    OrderDoesNotDivide,
+    /// A verification did not finish within its time budget.
+    VerifyTimeout,
+    /// `ZKPSystem::self_test` failed at the named step.
+    SelfTestFailed(&'static str),
+    /// The session cap is reached and new challenges are refused.
+    SessionCapacityExceeded,
+    /// A decoded number is not below its allowed maximum.
+    ValueOutOfRange,
+    /// The user already has a pending challenge and only one is allowed.
+    ChallengeAlreadyPending,
+    /// No challenge could be found that the user does not already have
+    /// pending.
+    ChallengeSpaceExhausted,
+    /// A required group parameter was not given to the builder.
+    MissingParameter(&'static str),
+    /// A group parameter given as a string is not decimal or `0x`-prefixed
+    /// hex.
+    InvalidNumber(&'static str),
+    /// The modulus `p` is shorter than the configured minimum.
+    ModulusTooSmall,
+    /// The challenge modulus `c_max` is not between 1 and `q`.
+    InvalidChallengeModulus,
+    /// `alpha` and `beta` are equal.
+    GeneratorsNotDistinct,
+    /// `p` or `q` is not prime, or `q` does not divide `p - 1`.
+    InvalidParameters,
+    /// `alpha` or `beta` does not have order `q`.
+    GeneratorNotInSubgroup,
+    /// The precompute window is not between 1 and 16.
+    InvalidPrecomputeWindow,
+}
+
+impl fmt::Display for ZKPError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZKPError::ElementOutOfRange => write!(f, "Group element out of range"),
+            ZKPError::ElementNotInSubgroup => write!(f, "Value is not in the prime-order subgroup"),
+            ZKPError::UnsupportedProofVersion(v) => write!(f, "Unsupported proof version {}", v),
+            ZKPError::MalformedProof => write!(f, "Malformed proof encoding"),
+            ZKPError::UnsupportedParametersVersion(v) => {
+                write!(f, "Unsupported parameters version {}", v)
+            }
+            ZKPError::MalformedParameters => write!(f, "Malformed parameters encoding"),
+            ZKPError::SelfVerificationFailed => {
+                write!(f, "Response failed local verification")
+            }
+            ZKPError::DuplicateGroupId(id) => write!(f, "Group '{}' is already registered", id),
+            ZKPError::UserNotFound => write!(f, "User not found"),
+            ZKPError::SessionNotFound => write!(f, "Session not found"),
+            ZKPError::AuthenticationFailed => write!(f, "Authentication failed"),
+            ZKPError::ProofOfPossessionRequired => {
+                write!(f, "Registration requires a proof of possession")
+            }
+            ZKPError::InvalidProofOfPossession => write!(f, "Proof of possession failed"),
+            ZKPError::ChallengeOutOfRange => {
+                write!(f, "Challenge is not below the group order")
+            }
+            ZKPError::SecretUnsealFailed => {
+                write!(
+                    f,
+                    "Could not open sealed secret (wrong passphrase or corrupted data)"
+                )
+            }
+            ZKPError::NegativeParameter(name) => {
+                write!(f, "Parameter {} must not be negative", name)
+            }
+            ZKPError::NonCanonicalEncoding => write!(f, "Non-canonical number encoding"),
+            ZKPError::OrderDoesNotDivide => write!(f, "Group order q does not divide p - 1"),
+            ZKPError::VerifyTimeout => write!(f, "Verification timed out"),
+            ZKPError::SelfTestFailed(step) => write!(f, "Self-test failed: {}", step),
+            ZKPError::ValueOutOfRange => write!(f, "Value is not below its allowed maximum"),
+            ZKPError::SessionCapacityExceeded => {
+                write!(f, "SessionCapacityExceeded: too many pending challenges")
+            }
+            ZKPError::ChallengeAlreadyPending => {
+                write!(
+                    f,
+                    "ChallengeAlreadyPending: answer the outstanding challenge first"
+                )
+            }
+            ZKPError::ChallengeSpaceExhausted => {
+                write!(
+                    f,
+                    "ChallengeSpaceExhausted: every challenge is already pending for this user"
+                )
+            }
+            ZKPError::MissingParameter("q") => {
+                write!(f, "Order q is required unless p is a safe prime")
+            }
+            ZKPError::MissingParameter(name) => write!(f, "Parameter {} is required", name),
+            ZKPError::InvalidNumber(name) => {
+                write!(
+                    f,
+                    "InvalidNumber: {} must be decimal or 0x-prefixed hex",
+                    name
+                )
+            }
+            ZKPError::ModulusTooSmall => {
+                write!(
+                    f,
+                    "ModulusTooSmall: p is shorter than the configured minimum"
+                )
+            }
+            ZKPError::InvalidChallengeModulus => {
+                write!(f, "InvalidChallengeModulus: c_max must be between 1 and q")
+            }
+            ZKPError::GeneratorsNotDistinct => {
+                write!(f, "GeneratorsNotDistinct: alpha and beta must differ")
+            }
+            ZKPError::InvalidParameters => write!(
+                f,
+                "InvalidParameters: p and q must be prime with q dividing p - 1"
+            ),
+            ZKPError::GeneratorNotInSubgroup => {
+                write!(
+                    f,
+                    "GeneratorNotInSubgroup: alpha and beta must have order q"
+                )
+            }
+            ZKPError::InvalidPrecomputeWindow => {
+                write!(f, "Precompute window must be between 1 and 16")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ZKPError {}
+
+/// Failures on the prover's side of the protocol.
+///
+/// Converts into the matching `ZKPError` variant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProverError {
+    /// The prover's own response does not verify against its commitments.
+    SelfVerificationFailed,
+}
+
+impl From<ProverError> for ZKPError {
+    fn from(e: ProverError) -> Self {
+        match e {
+            ProverError::SelfVerificationFailed => ZKPError::SelfVerificationFailed,
+        }
+    }
+}
+
+impl fmt::Display for ProverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        ZKPError::from(self.clone()).fmt(f)
+    }
+}
+
+impl std::error::Error for ProverError {}
+
+/// Failures on the verifier's side when checking a response.
+///
+/// Converts into the matching `ZKPError` variant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifierError {
+    /// No pending challenge exists for the given auth id.
+    SessionNotFound,
+    /// No user is registered under the given name.
+    UserNotFound,
+    /// The proof did not verify.
+    AuthenticationFailed,
+}
+
+impl From<VerifierError> for ZKPError {
+    fn from(e: VerifierError) -> Self {
+        match e {
+            VerifierError::SessionNotFound => ZKPError::SessionNotFound,
+            VerifierError::UserNotFound => ZKPError::UserNotFound,
+            VerifierError::AuthenticationFailed => ZKPError::AuthenticationFailed,
+        }
+    }
+}
+
+impl fmt::Display for VerifierError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        ZKPError::from(self.clone()).fmt(f)
+    }
+}
+
+impl std::error::Error for VerifierError {}
@@ -0,0 +1,125 @@
+use crate::error::ZKPError;
+use crate::system::ZKPParameters;
+use num_bigint::BigUint;
+use std::borrow::Cow;
+
+/// The order-`q` subgroup of Z_p*, and the arithmetic in it.
+///
+/// Holds only the group, not the generators or any protocol choice, so it
+/// can back protocols other than Chaum-Pedersen. `ZKPParameters::group`
+/// borrows the parameters' `p` and `q`; `Group::new` owns its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group<'a> {
+    p: Cow<'a, BigUint>,
+    q: Cow<'a, BigUint>,
+}
+
+impl Group<'static> {
+    /// Creates the order-`q` subgroup of Z_p*.
+    ///
+    /// Neither value is checked; `q` is expected to be a prime dividing
+    /// `p - 1`.
+    pub fn new(p: BigUint, q: BigUint) -> Self {
+        Self {
+            p: Cow::Owned(p),
+            q: Cow::Owned(q),
+        }
+    }
+}
+
+impl<'a> Group<'a> {
+    /// Borrows `p` and `q` instead of copying them.
+    pub(crate) fn borrowed(p: &'a BigUint, q: &'a BigUint) -> Self {
+        Self {
+            p: Cow::Borrowed(p),
+            q: Cow::Borrowed(q),
+        }
+    }
+
+    /// Returns the prime modulus `p`.
+    pub fn modulus(&self) -> &BigUint {
+        &self.p
+    }
+
+    /// Returns the prime order `q` of the subgroup.
+    pub fn order(&self) -> &BigUint {
+        &self.q
+    }
+
+    /// Computes `base^exponent mod p`.
+    pub fn pow(&self, base: &BigUint, exponent: &BigUint) -> BigUint {
+        base.modpow(exponent, &self.p)
+    }
+
+    /// Computes `a * b mod p`.
+    pub fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b % &*self.p
+    }
+
+    /// Reduces `v` modulo `p`.
+    pub fn reduce(&self, v: &BigUint) -> BigUint {
+        v % &*self.p
+    }
+
+    /// Reduces the exponent `e` modulo `q`.
+    pub fn reduce_exponent(&self, e: &BigUint) -> BigUint {
+        e % &*self.q
+    }
+
+    /// Checks whether `v` is an element of the subgroup.
+    ///
+    /// 1 <= v < p and v^q mod p == 1
+    pub fn subgroup_contains(&self, v: &BigUint) -> bool {
+        let one = BigUint::from(1u32);
+        *v >= one && *v < *self.p && v.modpow(&self.q, &self.p) == one
+    }
+}
+
+/// An element of the order-`q` subgroup of Z_p*, validated on construction.
+///
+/// Code that receives a `GroupElement` can rely on `1 <= v < p` and
+/// `v^q mod p == 1` without re-checking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupElement(BigUint);
+
+impl GroupElement {
+    /// Validates `value` against the group described by `params`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(GroupElement)` if `value` is in the subgroup.
+    /// * `Err(ZKPError::ElementOutOfRange)` if `value` is not in `[1, p)`.
+    /// * `Err(ZKPError::ElementNotInSubgroup)` if `value^q mod p != 1`.
+    pub fn new(params: &ZKPParameters, value: BigUint) -> Result<Self, ZKPError> {
+        if value == BigUint::from(0u32) || value >= params.p {
+            return Err(ZKPError::ElementOutOfRange);
+        }
+        if !params.subgroup_contains(&value) {
+            return Err(ZKPError::ElementNotInSubgroup);
+        }
+        Ok(Self(value))
+    }
+
+    /// Decodes a big-endian byte string and validates it as a group element.
+    pub fn try_from_bytes(params: &ZKPParameters, bytes: &[u8]) -> Result<Self, ZKPError> {
+        Self::new(params, BigUint::from_bytes_be(bytes))
+    }
+
+    /// Returns a reference to the underlying value.
+    pub fn value(&self) -> &BigUint {
+        &self.0
+    }
+
+    /// Consumes the element, returning the underlying value.
+    pub fn into_inner(self) -> BigUint {
+        self.0
+    }
+}
+
+impl TryFrom<(&ZKPParameters, &[u8])> for GroupElement {
+    type Error = ZKPError;
+
+    fn try_from((params, bytes): (&ZKPParameters, &[u8])) -> Result<Self, Self::Error> {
+        Self::try_from_bytes(params, bytes)
+    }
+}
@@ -5,19 +5,61 @@
 
 
 pub mod actors;
+pub mod audit;
+pub mod challenge;
+#[cfg(any(test, feature = "test-util"))]
+pub mod conformance;
+pub mod error;
+pub mod group;
+pub mod metrics;
+pub mod precompute;
 pub mod protocol;
+pub mod registry;
+pub mod replay;
+pub mod secret;
+pub mod service;
+pub mod session;
 pub mod system;
+pub mod transcript;
+pub mod transport;
 pub mod utils;
 pub mod auth_capnp;
 
-pub use actors::{Prover, Verifier};
+pub use actors::{
+    NonInteractiveProof, ProofInput, Prover, Signature, Verifier, VerifyOutcome,
+};
+pub use audit::{AuditEvent, AuthAuditor, NoopAuditor};
+pub use challenge::{ChallengeSource, RandomChallenges};
+pub use error::{ProverError, VerifierError, ZKPError};
+pub use group::{Group, GroupElement};
+pub use metrics::{MetricsSink, NoopMetrics};
+#[cfg(feature = "prometheus")]
+pub use metrics::PrometheusMetrics;
+pub use precompute::FixedBaseTable;
 pub use protocol::ZKPProtocol;
-pub use system::{ZKPParameters, ZKPSystem, ZKPSystemBuilder};
+pub use registry::GroupRegistry;
+pub use replay::ReplayCache;
+pub use secret::SecretProvider;
+pub use service::{AuthService, RegistrationRecord};
+pub use session::{EvictionPolicy, PendingChallenge, PerUserPolicy, SessionManager};
+pub use system::{ResponseConvention, ZKPConfig, ZKPParameters, ZKPSystem, ZKPSystemBuilder};
+pub use transcript::Transcript;
+pub use transport::{
+    run_prover, run_verifier, ProverTransport, PublicValueStore, VerifierTransport,
+};
 pub use utils::ZKPUtils;
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+    use futures::StreamExt;
+    use num_bigint::BigUint;
+    use sha2::{Sha256, Sha512};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use transport::PublicValueStore;
 
     #[test]
     fn test_with_builder_pattern() {
@@ -50,6 +92,34 @@ mod test {
         assert!(result);
     }
 
+    #[test]
+    fn test_builder_default() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+
+        let system = ZKPSystemBuilder::default()
+            .with_prime(p)
+            .with_order(q)
+            .with_generator(alpha)
+            .with_second_generator(beta)
+            .build()
+            .expect("Failed to build ZKP system");
+
+        let secret = ZKPUtils::generate_random_below(system.get_order());
+        let prover = Prover::new(&system, secret);
+        let (commitments, randomness) = prover.generate_commitments();
+
+        let verifier = Verifier::new(&system);
+        let challenge = verifier.generate_challenge();
+        let response = prover.generate_response(&challenge, &randomness);
+
+        assert!(verifier.verify(
+            (&commitments.0, &commitments.1),
+            &challenge,
+            &response,
+            (&prover.public_values().0, &prover.public_values().1),
+        ));
+    }
+
     #[test]
     fn test_1024_bits_constants() {
         let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
@@ -97,4 +167,3004 @@ mod test {
 
         assert!(result);
     }
+
+    #[test]
+    fn test_challenge_commit_reveal() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+
+        let secret = ZKPUtils::generate_random_below(system.get_order());
+        let prover = Prover::new(&system, secret);
+        let verifier = Verifier::new(&system);
+
+        let (challenge_commitment, challenge) = verifier.commit_challenge();
+        let (commitments, randomness) = prover.generate_commitments();
+
+        assert!(prover.verify_challenge_commitment(&challenge_commitment, &challenge));
+
+        let response = prover.generate_response(&challenge, &randomness);
+        assert!(verifier.verify(
+            (&commitments.0, &commitments.1),
+            &challenge,
+            &response,
+            (&prover.public_values().0, &prover.public_values().1),
+        ));
+    }
+
+    #[test]
+    fn test_challenge_commit_reveal_mismatch() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+
+        let secret = ZKPUtils::generate_random_below(system.get_order());
+        let prover = Prover::new(&system, secret);
+        let verifier = Verifier::new(&system);
+
+        let (challenge_commitment, challenge) = verifier.commit_challenge();
+        let swapped = challenge + 1u32;
+
+        assert!(!prover.verify_challenge_commitment(&challenge_commitment, &swapped));
+    }
+
+    #[test]
+    fn test_recover_secret_from_reused_nonce() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+
+        let secret = ZKPUtils::generate_random_below(system.get_order());
+        let prover = Prover::new(&system, secret.clone());
+        let verifier = Verifier::new(&system);
+
+        // Both challenges are answered with the same randomness k.
+        let (_, randomness) = prover.generate_commitments();
+        let c1 = verifier.generate_challenge();
+        let c2 = verifier.generate_challenge();
+        let s1 = prover.generate_response(&c1, &randomness);
+        let s2 = prover.generate_response(&c2, &randomness);
+
+        let recovered = system
+            .parameters()
+            .recover_secret_from_transcripts((&c1, &s1), (&c2, &s2));
+        assert_eq!(recovered, Some(secret));
+
+        assert_eq!(
+            system
+                .parameters()
+                .recover_secret_from_transcripts((&c1, &s1), (&c1, &s1)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_non_interactive_with_different_digests() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+
+        let secret = ZKPUtils::generate_random_below(system.get_order());
+        let prover = Prover::new(&system, secret);
+        let verifier = Verifier::new(&system);
+        let (y1, y2) = prover.public_values();
+
+        let sha256_proof = prover.prove_non_interactive_with::<Sha256>();
+        let sha512_proof = prover.prove_non_interactive_with::<Sha512>();
+        assert_ne!(sha256_proof, sha512_proof);
+
+        assert!(verifier.verify_non_interactive_with::<Sha256>(&sha256_proof, (y1, y2)));
+        assert!(verifier.verify_non_interactive_with::<Sha512>(&sha512_proof, (y1, y2)));
+        assert!(verifier.verify_non_interactive(&sha256_proof, (y1, y2)));
+
+        assert!(!verifier.verify_non_interactive_with::<Sha512>(&sha256_proof, (y1, y2)));
+        assert!(!verifier.verify_non_interactive_with::<Sha256>(&sha512_proof, (y1, y2)));
+    }
+
+    #[test]
+    fn test_subgroup_contains() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p.clone(), q, alpha.clone(), beta);
+        let params = system.parameters();
+
+        assert!(params.subgroup_contains(&BigUint::from(1u32)));
+        assert!(params.subgroup_contains(&alpha));
+        let secret = ZKPUtils::generate_random_below(system.get_order());
+        let (y1, y2) = params.compute_public_keys(&secret);
+        assert!(params.subgroup_contains(&y1));
+        assert!(params.subgroup_contains(&y2));
+
+        // p - 1 has order 2, which does not divide the odd prime q.
+        assert!(!params.subgroup_contains(&(&p - 1u32)));
+        assert!(!params.subgroup_contains(&BigUint::from(0u32)));
+        assert!(!params.subgroup_contains(&p));
+    }
+
+    enum Message {
+        Commitments(String, BigUint, BigUint),
+        Challenge(BigUint),
+        Response(BigUint),
+        Result(bool),
+    }
+
+    struct ChannelTransport {
+        user: String,
+        tx: UnboundedSender<Message>,
+        rx: UnboundedReceiver<Message>,
+    }
+
+    impl ChannelTransport {
+        fn pair(user: &str) -> (Self, Self) {
+            let (prover_tx, verifier_rx) = unbounded();
+            let (verifier_tx, prover_rx) = unbounded();
+            (
+                Self {
+                    user: user.to_string(),
+                    tx: prover_tx,
+                    rx: prover_rx,
+                },
+                Self {
+                    user: String::new(),
+                    tx: verifier_tx,
+                    rx: verifier_rx,
+                },
+            )
+        }
+
+        fn send(&mut self, message: Message) -> Result<(), &'static str> {
+            self.tx
+                .unbounded_send(message)
+                .map_err(|_| "channel closed")
+        }
+
+        async fn recv(&mut self) -> Result<Message, &'static str> {
+            self.rx.next().await.ok_or("channel closed")
+        }
+    }
+
+    impl ProverTransport for ChannelTransport {
+        type Error = &'static str;
+
+        async fn send_commitments(
+            &mut self,
+            commitments: (&BigUint, &BigUint),
+        ) -> Result<(), Self::Error> {
+            let message = Message::Commitments(
+                self.user.clone(),
+                commitments.0.clone(),
+                commitments.1.clone(),
+            );
+            self.send(message)
+        }
+
+        async fn recv_challenge(&mut self) -> Result<BigUint, Self::Error> {
+            match self.recv().await? {
+                Message::Challenge(c) => Ok(c),
+                _ => Err("expected challenge"),
+            }
+        }
+
+        async fn send_response(&mut self, response: &BigUint) -> Result<(), Self::Error> {
+            self.send(Message::Response(response.clone()))
+        }
+
+        async fn recv_result(&mut self) -> Result<bool, Self::Error> {
+            match self.recv().await? {
+                Message::Result(accepted) => Ok(accepted),
+                _ => Err("expected result"),
+            }
+        }
+    }
+
+    impl VerifierTransport for ChannelTransport {
+        type Error = &'static str;
+
+        async fn recv_commitments(&mut self) -> Result<(String, (BigUint, BigUint)), Self::Error> {
+            match self.recv().await? {
+                Message::Commitments(user, r1, r2) => Ok((user, (r1, r2))),
+                _ => Err("expected commitments"),
+            }
+        }
+
+        async fn send_challenge(&mut self, challenge: &BigUint) -> Result<(), Self::Error> {
+            self.send(Message::Challenge(challenge.clone()))
+        }
+
+        async fn recv_response(&mut self) -> Result<BigUint, Self::Error> {
+            match self.recv().await? {
+                Message::Response(s) => Ok(s),
+                _ => Err("expected response"),
+            }
+        }
+
+        async fn send_result(&mut self, accepted: bool) -> Result<(), Self::Error> {
+            self.send(Message::Result(accepted))
+        }
+    }
+
+    #[test]
+    fn test_drivers_over_channel_transport() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+
+        let secret = ZKPUtils::generate_random_below(system.get_order());
+        let prover = Prover::new(&system, secret);
+        let verifier = Verifier::new(&system);
+
+        let mut store = HashMap::new();
+        store.insert("alice".to_string(), prover.public_values_owned());
+
+        for (user, expected) in [("alice", true), ("mallory", false)] {
+            let (mut prover_side, mut verifier_side) = ChannelTransport::pair(user);
+            let (prover_result, verifier_result) =
+                futures::executor::block_on(futures::future::join(
+                    run_prover(&prover, &mut prover_side),
+                    run_verifier(&verifier, &store, &mut verifier_side),
+                ));
+
+            assert_eq!(prover_result, Ok(expected));
+            assert_eq!(verifier_result, Ok(expected));
+        }
+    }
+
+    #[test]
+    fn test_group_element_from_bytes() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p.clone(), q, alpha.clone(), beta);
+        let params = system.parameters();
+
+        let element = GroupElement::try_from_bytes(params, &alpha.to_bytes_be())
+            .expect("alpha is a subgroup element");
+        assert_eq!(element.value(), &alpha);
+
+        let bytes = alpha.to_bytes_be();
+        assert!(GroupElement::try_from((params, bytes.as_slice())).is_ok());
+
+        assert_eq!(
+            GroupElement::try_from_bytes(params, &[]),
+            Err(ZKPError::ElementOutOfRange)
+        );
+        assert_eq!(
+            GroupElement::try_from_bytes(params, &p.to_bytes_be()),
+            Err(ZKPError::ElementOutOfRange)
+        );
+        assert_eq!(
+            GroupElement::try_from_bytes(params, &(&p - 1u32).to_bytes_be()),
+            Err(ZKPError::ElementNotInSubgroup)
+        );
+    }
+
+    #[test]
+    fn test_challenge_then_verify() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+
+        let secret = ZKPUtils::generate_random_below(system.get_order());
+        let prover = Prover::new(&system, secret);
+        let (y1, y2) = prover.public_values();
+
+        let (commitments, randomness) = prover.generate_commitments();
+        assert!(system.challenge_then_verify(
+            (&commitments.0, &commitments.1),
+            (y1, y2),
+            |challenge| prover.generate_response(challenge, &randomness),
+        ));
+
+        let (commitments, randomness) = prover.generate_commitments();
+        assert!(!system.challenge_then_verify(
+            (&commitments.0, &commitments.1),
+            (y1, y2),
+            |challenge| prover.generate_response(&(challenge + 1u32), &randomness),
+        ));
+    }
+
+    #[test]
+    fn test_verify_reduction_matches_modpow_reduction() {
+        let (alpha, beta, p, q) = ZKPUtils::get_2048_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+        let params = system.parameters();
+        let one = BigUint::from(1u32);
+
+        let reference = |commitments: (&BigUint, &BigUint),
+                         c: &BigUint,
+                         s: &BigUint,
+                         public: (&BigUint, &BigUint)| {
+            let lhs1 = (params.alpha.modpow(s, &params.p) * public.0.modpow(c, &params.p))
+                .modpow(&one, &params.p);
+            let lhs2 = (params.beta.modpow(s, &params.p) * public.1.modpow(c, &params.p))
+                .modpow(&one, &params.p);
+            *commitments.0 == lhs1 && *commitments.1 == lhs2
+        };
+
+        let secret = ZKPUtils::generate_random_below(system.get_order());
+        let prover = Prover::new(&system, secret);
+        let (y1, y2) = prover.public_values();
+        let (commitments, randomness) = prover.generate_commitments();
+        let c = ZKPUtils::generate_random_below(system.get_order());
+        let s = prover.generate_response(&c, &randomness);
+
+        let valid = ((&commitments.0, &commitments.1), &c, &s, (y1, y2));
+        assert!(params.verify(valid.0, valid.1, valid.2, valid.3));
+        assert!(reference(valid.0, valid.1, valid.2, valid.3));
+
+        for _ in 0..8 {
+            let r1 = ZKPUtils::generate_random_below(&params.p);
+            let r2 = ZKPUtils::generate_random_below(&params.p);
+            let c = ZKPUtils::generate_random_below(&params.q);
+            let s = ZKPUtils::generate_random_below(&params.q);
+            assert_eq!(
+                params.verify((&r1, &r2), &c, &s, (y1, y2)),
+                reference((&r1, &r2), &c, &s, (y1, y2))
+            );
+        }
+    }
+
+    /// Stands in for an HSM by forwarding to the in-memory math.
+    struct MockSecretProvider {
+        secret: BigUint,
+    }
+
+    impl SecretProvider for MockSecretProvider {
+        fn public_values<T: ZKPProtocol>(&self, system: &T) -> (BigUint, BigUint) {
+            system.compute_public_values(&self.secret)
+        }
+
+        fn respond<T: ZKPProtocol>(
+            &self,
+            system: &T,
+            randomness: &BigUint,
+            challenge: &BigUint,
+        ) -> BigUint {
+            system.compute_response(randomness, challenge, &self.secret)
+        }
+    }
+
+    #[test]
+    fn test_prover_with_secret_provider() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+
+        let secret = ZKPUtils::generate_random_below(system.get_order());
+        let provider = MockSecretProvider {
+            secret: secret.clone(),
+        };
+        let prover = Prover::new(&system, provider);
+        assert_eq!(
+            prover.public_values(),
+            Prover::new(&system, secret).public_values()
+        );
+
+        let verifier = Verifier::new(&system);
+        let (commitments, randomness) = prover.generate_commitments();
+        let challenge = verifier.generate_challenge();
+        let response = prover.generate_response(&challenge, &randomness);
+
+        assert!(verifier.verify(
+            (&commitments.0, &commitments.1),
+            &challenge,
+            &response,
+            (&prover.public_values().0, &prover.public_values().1),
+        ));
+    }
+
+    #[test]
+    fn test_prover_computes_public_values_lazily() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct Counting<'c> {
+            inner: MockSecretProvider,
+            computed: &'c AtomicUsize,
+        }
+
+        impl SecretProvider for Counting<'_> {
+            fn public_values<T: ZKPProtocol>(&self, system: &T) -> (BigUint, BigUint) {
+                self.computed.fetch_add(1, Ordering::SeqCst);
+                self.inner.public_values(system)
+            }
+
+            fn respond<T: ZKPProtocol>(
+                &self,
+                system: &T,
+                randomness: &BigUint,
+                challenge: &BigUint,
+            ) -> BigUint {
+                self.inner.respond(system, randomness, challenge)
+            }
+        }
+
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+        let secret = ZKPUtils::generate_random_below(system.get_order());
+        let (y1, y2) = system.compute_public_values(&secret);
+        let computed = AtomicUsize::new(0);
+        let mut prover = Prover::new(
+            &system,
+            Counting {
+                inner: MockSecretProvider {
+                    secret: secret.clone(),
+                },
+                computed: &computed,
+            },
+        );
+
+        // Neither does formatting the prover.
+        let _ = format!("{:?}", prover);
+        assert_eq!(computed.load(Ordering::SeqCst), 0);
+
+        // A login against stored values never needs them.
+        let verifier = Verifier::new(&system);
+        let ((r1, r2), k) = prover.generate_commitments();
+        let c = verifier.generate_challenge();
+        let s = prover.generate_response(&c, &k);
+        assert!(verifier.verify((&r1, &r2), &c, &s, (&y1, &y2)));
+        assert_eq!(computed.load(Ordering::SeqCst), 0);
+
+        assert_eq!(prover.public_values(), &(y1, y2));
+        assert_eq!(prover.public_values_owned(), *prover.public_values());
+        assert_eq!(computed.load(Ordering::SeqCst), 1);
+
+        let rotated = &secret + 1u32;
+        prover.change_secret(Counting {
+            inner: MockSecretProvider {
+                secret: rotated.clone(),
+            },
+            computed: &computed,
+        });
+        assert_eq!(computed.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            prover.public_values(),
+            &system.compute_public_values(&rotated)
+        );
+        assert_eq!(computed.load(Ordering::SeqCst), 2);
+    }
+    #[test]
+    fn test_proof_serialization_versioning() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+
+        let secret = ZKPUtils::generate_random_below(system.get_order());
+        let prover = Prover::new(&system, secret);
+        let verifier = Verifier::new(&system);
+        let (y1, y2) = prover.public_values();
+
+        let proof = prover.prove_non_interactive();
+        let blob = proof.to_bytes();
+        assert_eq!(blob[0], NonInteractiveProof::VERSION);
+
+        let decoded = NonInteractiveProof::from_bytes(&blob).expect("v1 blob must parse");
+        assert_eq!(decoded, proof);
+        assert!(verifier.verify_non_interactive(&decoded, (y1, y2)));
+
+        let mut bumped = blob.clone();
+        bumped[0] = NonInteractiveProof::VERSION + 1;
+        assert_eq!(
+            NonInteractiveProof::from_bytes(&bumped),
+            Err(ZKPError::UnsupportedProofVersion(
+                NonInteractiveProof::VERSION + 1
+            ))
+        );
+
+        assert_eq!(
+            NonInteractiveProof::from_bytes(&blob[..blob.len() - 1]),
+            Err(ZKPError::MalformedProof)
+        );
+        assert_eq!(
+            NonInteractiveProof::from_bytes(&[]),
+            Err(ZKPError::MalformedProof)
+        );
+    }
+
+    #[test]
+    fn test_generate_random_in_range() {
+        let low = BigUint::from(10u32);
+        let high = BigUint::from(15u32);
+
+        let mut counts = [0u32; 5];
+        for _ in 0..5000 {
+            let v = ZKPUtils::generate_random_in_range(&low, &high);
+            assert!(v >= low && v < high);
+            let offset: u32 = (v - &low).try_into().unwrap();
+            counts[offset as usize] += 1;
+        }
+
+        // Each bucket expects 1000 hits; allow a generous margin.
+        for count in counts {
+            assert!((800..1200).contains(&count), "skewed counts: {:?}", counts);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_generate_random_in_empty_range() {
+        let v = BigUint::from(7u32);
+        ZKPUtils::generate_random_in_range(&v, &v);
+    }
+
+    #[test]
+    fn test_replay_cache_rejects_duplicate_proof() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+
+        let secret = ZKPUtils::generate_random_below(system.get_order());
+        let prover = Prover::new(&system, secret);
+        let verifier = Verifier::new(&system);
+        let (y1, y2) = prover.public_values();
+        let mut cache = ReplayCache::new(2);
+
+        let transcript = || {
+            let (commitments, randomness) = prover.generate_commitments();
+            let challenge = verifier.generate_challenge();
+            let response = prover.generate_response(&challenge, &randomness);
+            (commitments, challenge, response)
+        };
+        let verify = |cache: &mut ReplayCache, t: &((BigUint, BigUint), BigUint, BigUint)| {
+            verifier.verify_with_replay_protection(cache, (&t.0 .0, &t.0 .1), &t.1, &t.2, (y1, y2))
+        };
+
+        let first = transcript();
+        assert!(verify(&mut cache, &first));
+        assert!(!verify(&mut cache, &first));
+
+        // Two newer proofs push the first one out of a capacity-2 cache.
+        let second = transcript();
+        let third = transcript();
+        assert!(verify(&mut cache, &second));
+        assert!(verify(&mut cache, &third));
+        assert_eq!(cache.len(), 2);
+        assert!(!verify(&mut cache, &third));
+        assert!(verify(&mut cache, &first));
+    }
+
+    #[test]
+    fn test_generate_response_checked() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+
+        let secret = ZKPUtils::generate_random_below(system.get_order());
+        let prover = Prover::new(&system, secret);
+        let verifier = Verifier::new(&system);
+
+        let (commitments, randomness) = prover.generate_commitments();
+        let challenge = verifier.generate_challenge();
+        let response = prover
+            .generate_response_checked((&commitments.0, &commitments.1), &challenge, &randomness)
+            .expect("honest transcript must self-verify");
+        assert_eq!(response, prover.generate_response(&challenge, &randomness));
+
+        let corrupted = &randomness + 1u32;
+        assert_eq!(
+            prover.generate_response_checked(
+                (&commitments.0, &commitments.1),
+                &challenge,
+                &corrupted
+            ),
+            Err(ProverError::SelfVerificationFailed)
+        );
+    }
+
+    #[test]
+    fn test_group_registry() {
+        let mut registry = GroupRegistry::new();
+
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let builtin = registry.get("modp-1024").expect("built-in group");
+        assert_eq!(
+            (&builtin.p, &builtin.q, &builtin.alpha, &builtin.beta),
+            (&p, &q, &alpha, &beta)
+        );
+
+        let modp_2048 = registry.get("modp-2048").expect("built-in group");
+        assert!(modp_2048.subgroup_contains(&modp_2048.beta));
+        assert_eq!(
+            GroupRegistry::new().get("modp-2048").unwrap().beta,
+            modp_2048.beta
+        );
+        assert!(registry.get("modp-4096").is_none());
+
+        let custom = ZKPParameters {
+            p: BigUint::from(23u32),
+            q: BigUint::from(11u32),
+            alpha: BigUint::from(4u32),
+            beta: BigUint::from(9u32),
+            convention: ResponseConvention::Subtractive,
+            c_max: None,
+        };
+        registry.register("toy-23", custom.clone()).unwrap();
+        assert_eq!(registry.get("toy-23").unwrap().p, custom.p);
+        assert_eq!(
+            registry.register("modp-1024", custom),
+            Err(ZKPError::DuplicateGroupId("modp-1024".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_transcript_display_truncates() {
+        let long = BigUint::parse_bytes(b"0123456789abcdef0123456789abcdef", 16).unwrap();
+        let transcript = Transcript {
+            public_values: (long.clone(), BigUint::from(0xabcu32)),
+            commitments: (long.clone(), long.clone()),
+            challenge: BigUint::from(7u32),
+            response: long.clone(),
+        };
+
+        assert_eq!(
+            transcript.to_string(),
+            "Transcript { y1: 12345678...89abcdef, y2: abc, r1: 12345678...89abcdef, \
+             r2: 12345678...89abcdef, c: 7, s: 12345678...89abcdef }"
+        );
+        assert!(format!("{:?}", transcript).contains(&long.to_string()));
+    }
+
+    #[test]
+    fn test_response_conventions() {
+        let build = |convention| {
+            let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+            ZKPSystem::builder()
+                .with_prime(p)
+                .with_order(q)
+                .with_generator(alpha)
+                .with_second_generator(beta)
+                .with_response_convention(convention)
+                .build()
+                .unwrap()
+        };
+        let subtractive = build(ResponseConvention::Subtractive);
+        let additive = build(ResponseConvention::Additive);
+        let secret = ZKPUtils::generate_random_below(subtractive.get_order());
+
+        for (system, other) in [(&subtractive, &additive), (&additive, &subtractive)] {
+            let prover = Prover::new(system, secret.clone());
+            let (y1, y2) = prover.public_values();
+            let (commitments, randomness) = prover.generate_commitments();
+            let challenge = Verifier::new(system).generate_challenge();
+            let response = prover.generate_response(&challenge, &randomness);
+            let commitments = (&commitments.0, &commitments.1);
+
+            assert!(Verifier::new(system).verify(commitments, &challenge, &response, (y1, y2)));
+            assert!(!Verifier::new(other).verify(commitments, &challenge, &response, (y1, y2)));
+        }
+    }
+
+    #[test]
+    fn test_recover_secret_additive_convention() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::builder()
+            .with_prime(p)
+            .with_order(q)
+            .with_generator(alpha)
+            .with_second_generator(beta)
+            .with_response_convention(ResponseConvention::Additive)
+            .build()
+            .unwrap();
+
+        let secret = ZKPUtils::generate_random_below(system.get_order());
+        let prover = Prover::new(&system, secret.clone());
+        let (_, randomness) = prover.generate_commitments();
+        let c1 = BigUint::from(3u32);
+        let c2 = BigUint::from(5u32);
+        let s1 = prover.generate_response(&c1, &randomness);
+        let s2 = prover.generate_response(&c2, &randomness);
+
+        assert_eq!(
+            system
+                .parameters()
+                .recover_secret_from_transcripts((&c1, &s1), (&c2, &s2)),
+            Some(secret)
+        );
+    }
+
+    #[test]
+    fn test_derive_new_beta() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p.clone(), q.clone(), alpha.clone(), beta.clone());
+        let params = system.parameters();
+
+        let derived = params.derive_new_beta(b"rotation-2026");
+        assert!(params.subgroup_contains(&derived));
+        assert_ne!(derived, BigUint::from(1u32));
+        assert_ne!(derived, beta);
+        assert_eq!(derived, params.derive_new_beta(b"rotation-2026"));
+        assert_ne!(derived, params.derive_new_beta(b"rotation-2027"));
+
+        let rotated = ZKPSystem::new(p, q, alpha, derived);
+        let secret = ZKPUtils::generate_random_below(rotated.get_order());
+        let prover = Prover::new(&rotated, secret);
+        let verifier = Verifier::new(&rotated);
+        let proof = prover.prove_non_interactive();
+        let (y1, y2) = prover.public_values();
+        assert!(verifier.verify_non_interactive(&proof, (y1, y2)));
+    }
+
+    #[test]
+    fn test_is_probable_prime() {
+        let primes = [2u32, 3, 5, 11, 23, 7919, 1_000_003];
+        let composites = [0u32, 1, 4, 9, 561, 7917, 1_000_001];
+        for n in primes {
+            assert!(
+                ZKPUtils::is_probable_prime(&BigUint::from(n), 20),
+                "{} is prime",
+                n
+            );
+        }
+        for n in composites {
+            assert!(
+                !ZKPUtils::is_probable_prime(&BigUint::from(n), 20),
+                "{} is composite",
+                n
+            );
+        }
+
+        let (_, _, p, q) = ZKPUtils::get_1024_bit_constants();
+        assert!(ZKPUtils::is_probable_prime(&p, 20));
+        assert!(ZKPUtils::is_probable_prime(&q, 20));
+    }
+
+    #[test]
+    fn test_builder_derives_order_for_safe_prime() {
+        // 23 = 2 * 11 + 1; 4 and 9 generate the order-11 subgroup.
+        let system = ZKPSystem::builder()
+            .with_prime(BigUint::from(23u32))
+            .with_generator(BigUint::from(4u32))
+            .with_second_generator(BigUint::from(9u32))
+            .build()
+            .expect("q is derivable from a safe prime");
+        assert_eq!(system.get_order(), &BigUint::from(11u32));
+
+        let secret = BigUint::from(7u32);
+        let prover = Prover::new(&system, secret);
+        let proof = prover.prove_non_interactive();
+        let (y1, y2) = prover.public_values();
+        assert!(Verifier::new(&system).verify_non_interactive(&proof, (y1, y2)));
+    }
+
+    #[test]
+    fn test_builder_requires_order_for_non_safe_prime() {
+        let (alpha, beta, p, _) = ZKPUtils::get_1024_bit_constants();
+        let result = ZKPSystem::builder()
+            .with_prime(p)
+            .with_generator(alpha)
+            .with_second_generator(beta)
+            .build();
+        assert!(result.is_err());
+
+        let result = ZKPSystem::builder()
+            .with_prime(BigUint::from(29u32))
+            .with_generator(BigUint::from(4u32))
+            .with_second_generator(BigUint::from(9u32))
+            .build();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_verify_many_challenges() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+
+        let secret = ZKPUtils::generate_random_below(system.get_order());
+        let prover = Prover::new(&system, secret);
+        let verifier = Verifier::new(&system);
+        let (y1, y2) = prover.public_values();
+
+        let (commitments, randomness) = prover.generate_commitments();
+        let answers: Vec<(BigUint, BigUint)> = (0..2)
+            .map(|_| {
+                let c = verifier.generate_challenge();
+                let s = prover.generate_response(&c, &randomness);
+                (c, s)
+            })
+            .chain(std::iter::once((BigUint::from(1u32), BigUint::from(1u32))))
+            .collect();
+
+        assert_eq!(
+            verifier.verify_many_challenges((&commitments.0, &commitments.1), (y1, y2), &answers),
+            vec![true, true, false]
+        );
+    }
+
+    #[test]
+    fn test_parameters_from_bigint() {
+        use num_bigint::BigInt;
+
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let params = ZKPParameters::from_bigint(
+            BigInt::from(p.clone()),
+            BigInt::from(q.clone()),
+            BigInt::from(alpha.clone()),
+            BigInt::from(beta.clone()),
+        )
+        .expect("positive values convert");
+        assert_eq!(
+            (params.p, params.q, params.alpha, params.beta),
+            (p, q, alpha, beta)
+        );
+
+        let result = ZKPParameters::from_bigint(
+            BigInt::from(23),
+            BigInt::from(11),
+            BigInt::from(-4),
+            BigInt::from(9),
+        );
+        assert_eq!(result.unwrap_err(), ZKPError::NegativeParameter("alpha"));
+    }
+
+    #[test]
+    fn test_auth_service_flow() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let service = AuthService::new(std::sync::Arc::new(ZKPSystem::new(p, q, alpha, beta)));
+        let prover = Prover::new(
+            service.system(),
+            ZKPUtils::generate_random_below(service.system().get_order()),
+        );
+        let (y1, y2) = prover.public_values_owned();
+        service.register("alice", y1, y2).unwrap();
+        assert_eq!(service.registered_users(), 1);
+
+        let ((r1, r2), k) = prover.generate_commitments();
+        let (auth_id, c) = service.create_challenge("alice", r1, r2).unwrap();
+        assert_eq!(service.active_sessions(), 1);
+
+        let s = prover.generate_response(&c, &k);
+        assert!(service.verify(&auth_id, &s).is_ok());
+        assert_eq!(service.active_sessions(), 0);
+        assert_eq!(
+            service.verify(&auth_id, &s).unwrap_err(),
+            VerifierError::SessionNotFound
+        );
+    }
+
+    #[test]
+    fn test_auth_service_rejections() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let outside = &p - 1u32;
+        let service = AuthService::new(std::sync::Arc::new(ZKPSystem::new(p, q, alpha, beta)));
+        let prover = Prover::new(
+            service.system(),
+            ZKPUtils::generate_random_below(service.system().get_order()),
+        );
+        let (y1, y2) = prover.public_values_owned();
+
+        assert_eq!(
+            service
+                .register("mallory", outside, y2.clone())
+                .unwrap_err(),
+            ZKPError::ElementNotInSubgroup
+        );
+        assert_eq!(service.registered_users(), 0);
+
+        let ((r1, r2), k) = prover.generate_commitments();
+        assert_eq!(
+            service
+                .create_challenge("alice", r1.clone(), r2.clone())
+                .unwrap_err(),
+            ZKPError::UserNotFound
+        );
+
+        service.register("alice", y1, y2).unwrap();
+        let (auth_id, c) = service.create_challenge("alice", r1, r2).unwrap();
+        let s = prover.generate_response(&(c + 1u32), &k);
+        assert_eq!(
+            service.verify(&auth_id, &s).unwrap_err(),
+            VerifierError::AuthenticationFailed
+        );
+        assert_eq!(service.active_sessions(), 0);
+    }
+
+    #[test]
+    fn test_auth_service_verifies_against_values_at_challenge_time() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let service = AuthService::new(std::sync::Arc::new(ZKPSystem::new(p, q, alpha, beta)));
+        let order = service.system().get_order();
+        let old = Prover::new(service.system(), ZKPUtils::generate_random_below(order));
+        let new = Prover::new(service.system(), ZKPUtils::generate_random_below(order));
+        let (y1, y2) = old.public_values_owned();
+        service.register("alice", y1, y2).unwrap();
+
+        let ((r1, r2), k) = old.generate_commitments();
+        let (pending_id, c) = service
+            .create_challenge("alice", r1.clone(), r2.clone())
+            .unwrap();
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let (y1, y2) = new.public_values_owned();
+                service.register("alice", y1, y2).unwrap();
+            });
+        });
+        assert_eq!(service.record("alice").unwrap().y1, new.public_values().0);
+
+        // The pending challenge still answers to the key it was issued for.
+        let s = old.generate_response(&c, &k);
+        assert!(service.verify(&pending_id, &s).is_ok());
+
+        // New challenges use the new key.
+        let (auth_id, c) = service.create_challenge("alice", r1, r2).unwrap();
+        let s = old.generate_response(&c, &k);
+        assert_eq!(
+            service.verify(&auth_id, &s).unwrap_err(),
+            VerifierError::AuthenticationFailed
+        );
+    }
+
+    #[test]
+    fn test_auth_service_reports_to_auditor() {
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        struct Recording(Mutex<Vec<(&'static str, AuditEvent)>>);
+
+        impl AuthAuditor for Recording {
+            fn on_register(&self, event: &AuditEvent) {
+                self.0.lock().unwrap().push(("register", event.clone()));
+            }
+            fn on_challenge(&self, event: &AuditEvent) {
+                self.0.lock().unwrap().push(("challenge", event.clone()));
+            }
+            fn on_verify_success(&self, event: &AuditEvent) {
+                self.0.lock().unwrap().push(("success", event.clone()));
+            }
+            fn on_verify_failure(&self, event: &AuditEvent) {
+                self.0.lock().unwrap().push(("failure", event.clone()));
+            }
+        }
+
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let auditor = Arc::new(Recording::default());
+        let service = AuthService::new(Arc::new(ZKPSystem::new(p, q, alpha, beta)))
+            .with_auditor(auditor.clone());
+        let prover = Prover::new(
+            service.system(),
+            ZKPUtils::generate_random_below(service.system().get_order()),
+        );
+        let (y1, y2) = prover.public_values_owned();
+        service.register("alice", y1.clone(), y2.clone()).unwrap();
+
+        let ((r1, r2), k) = prover.generate_commitments();
+        let (accepted, c) = service
+            .create_challenge("alice", r1.clone(), r2.clone())
+            .unwrap();
+        let s = prover.generate_response(&c, &k);
+        service.verify(&accepted, &s).unwrap();
+        let (rejected, _) = service
+            .create_challenge("alice", r1.clone(), r2.clone())
+            .unwrap();
+        service.verify(&rejected, &s).unwrap_err();
+        // Unknown ids are not tied to a user and go unreported.
+        service.verify(&rejected, &s).unwrap_err();
+
+        let events = auditor.0.lock().unwrap();
+        let kinds: Vec<_> = events
+            .iter()
+            .map(|(kind, event)| (*kind, event.auth_id.as_deref()))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("register", None),
+                ("challenge", Some(accepted.as_str())),
+                ("success", Some(accepted.as_str())),
+                ("challenge", Some(rejected.as_str())),
+                ("failure", Some(rejected.as_str())),
+            ]
+        );
+        assert!(events.iter().all(|(_, event)| event.user == "alice"));
+        assert_eq!(
+            events[0].1.fingerprint,
+            AuditEvent::fingerprint(&[&y1, &y2])
+        );
+        assert_eq!(
+            events[2].1.fingerprint,
+            AuditEvent::fingerprint(&[&y1, &y2, &r1, &r2, &c, &s])
+        );
+        assert_ne!(events[2].1.fingerprint, events[4].1.fingerprint);
+    }
+
+    #[test]
+    fn test_auth_service_regenerates_colliding_challenge() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let draws = Arc::new(AtomicUsize::new(0));
+        let counter = draws.clone();
+        let service = AuthService::new(Arc::new(ZKPSystem::new(p, q, alpha, beta)))
+            .with_challenge_generator(move || {
+                let sequence = [5u32, 5, 7];
+                BigUint::from(sequence[counter.fetch_add(1, Ordering::SeqCst) % 3])
+            });
+        let prover = Prover::new(
+            service.system(),
+            ZKPUtils::generate_random_below(service.system().get_order()),
+        );
+        let (y1, y2) = prover.public_values_owned();
+        service.register("alice", y1, y2).unwrap();
+
+        let ((r1, r2), _) = prover.generate_commitments();
+        let (_, first) = service
+            .create_challenge("alice", r1.clone(), r2.clone())
+            .unwrap();
+        let (_, second) = service.create_challenge("alice", r1, r2).unwrap();
+
+        assert_eq!(first, BigUint::from(5u32));
+        assert_eq!(second, BigUint::from(7u32));
+        assert_eq!(draws.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_auth_service_reports_exhausted_challenge_space() {
+        use std::sync::Arc;
+
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::builder()
+            .with_prime(p)
+            .with_order(q)
+            .with_generator(alpha)
+            .with_second_generator(beta)
+            .with_challenge_modulus(BigUint::from(2u32))
+            .build()
+            .unwrap();
+        let service = AuthService::new(Arc::new(system));
+        let prover = Prover::new(
+            service.system(),
+            ZKPUtils::generate_random_below(service.system().get_order()),
+        );
+        let (y1, y2) = prover.public_values_owned();
+        service.register("alice", y1, y2).unwrap();
+
+        let ((r1, r2), _) = prover.generate_commitments();
+        let mut issued: Vec<BigUint> = (0..2)
+            .map(|_| {
+                service
+                    .create_challenge("alice", r1.clone(), r2.clone())
+                    .unwrap()
+                    .1
+            })
+            .collect();
+        issued.sort();
+        assert_eq!(issued, [BigUint::from(0u32), BigUint::from(1u32)]);
+        assert_eq!(
+            service.create_challenge("alice", r1, r2),
+            Err(ZKPError::ChallengeSpaceExhausted)
+        );
+    }
+
+    #[test]
+    fn test_modpow_multi_matches_naive() {
+        let (alpha, beta, p, q) = ZKPUtils::get_2048_bit_constants();
+        let naive = |pairs: &[(&BigUint, &BigUint)], m: &BigUint| {
+            pairs.iter().fold(BigUint::from(1u32) % m, |acc, (b, e)| {
+                acc * b.modpow(e, m) % m
+            })
+        };
+
+        for _ in 0..8 {
+            let (s, c) = (
+                ZKPUtils::generate_random_below(&q),
+                ZKPUtils::generate_random_below(&q),
+            );
+            let y = ZKPUtils::generate_random_below(&p);
+            let pairs = [(&alpha, &s), (&y, &c)];
+            assert_eq!(ZKPUtils::modpow_multi(&pairs, &p), naive(&pairs, &p));
+        }
+
+        let zero = BigUint::from(0u32);
+        let big = &p + 5u32;
+        let three_terms = [(&alpha, &q), (&beta, &zero), (&big, &p)];
+        assert_eq!(
+            ZKPUtils::modpow_multi(&three_terms, &q),
+            naive(&three_terms, &q)
+        );
+        assert_eq!(ZKPUtils::modpow_multi(&[], &p), BigUint::from(1u32));
+        assert_eq!(
+            ZKPUtils::modpow_multi(&[(&alpha, &q)], &BigUint::from(1u32)),
+            zero
+        );
+    }
+
+    #[test]
+    fn test_verify_rejects_over_width_inputs_before_exponentiating() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let params = ZKPParameters::from((alpha, beta, p.clone(), q.clone()));
+        let x = ZKPUtils::generate_random_below(&q);
+        let k = ZKPUtils::generate_random_below(&q);
+        let c = ZKPUtils::generate_random_below(&q);
+        let (y1, y2) = params.compute_public_keys(&x);
+        let (r1, r2) = params.compute_commitments(&k);
+        let s = params.compute_response(&k, &c, &x);
+        let wide = &r1 + (&p << 8);
+
+        let calls = ZKPUtils::modpow_multi_calls_on_this_thread();
+        assert!(!params.verify((&wide, &r2), &c, &s, (&y1, &y2)));
+        assert!(!params.verify((&r1, &r2), &c, &s, (&y1, &(&y2 + (&p << 8)))));
+        assert_eq!(ZKPUtils::modpow_multi_calls_on_this_thread(), calls);
+
+        // A wrong value within the byte width of p still goes through the
+        // equations.
+        assert!(!params.verify((&r1, &r2), &c, &s, (&(&p - 1u32), &y2)));
+        assert!(params.verify((&r1, &r2), &c, &s, (&y1, &y2)));
+        assert_eq!(ZKPUtils::modpow_multi_calls_on_this_thread(), calls + 4);
+    }
+
+    #[test]
+    fn test_precomputed_verify_rejects_over_width_inputs_before_exponentiating() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::builder()
+            .with_prime(p.clone())
+            .with_order(q.clone())
+            .with_generator(alpha)
+            .with_second_generator(beta)
+            .with_precomputation()
+            .build()
+            .unwrap();
+        let x = ZKPUtils::generate_random_below(&q);
+        let k = ZKPUtils::generate_random_below(&q);
+        let c = ZKPUtils::generate_random_below(&q);
+        let (y1, y2) = system.compute_public_values(&x);
+        let (r1, r2) = system.compute_commitments(&k);
+        let s = system.compute_response(&k, &c, &x);
+        let wide = &r1 + (&p << 8);
+        let proof = |r1: &BigUint| ProofInput {
+            commitments: (r1.clone(), r2.clone()),
+            challenge: c.clone(),
+            response: s.clone(),
+            public_values: (y1.clone(), y2.clone()),
+        };
+
+        let pows = FixedBaseTable::pows_on_this_thread();
+        assert!(!system.verify((&wide, &r2), &c, &s, (&y1, &y2)));
+        assert_eq!(
+            system.verify_equations((&wide, &r2), &c, &s, (&y1, &y2)),
+            (false, false)
+        );
+        assert_eq!(system.verify_batch(&[proof(&wide)]), vec![false]);
+        assert!(!system
+            .parameters()
+            .verify_batch_combined(&[proof(&r1), proof(&wide)]));
+        assert_eq!(FixedBaseTable::pows_on_this_thread(), pows);
+
+        assert!(system.verify((&r1, &r2), &c, &s, (&y1, &y2)));
+        assert_eq!(system.verify_batch(&[proof(&r1)]), vec![true]);
+        assert_eq!(FixedBaseTable::pows_on_this_thread(), pows + 4);
+    }
+
+    #[test]
+    fn test_verify_challenge_at_order_boundary() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let x = ZKPUtils::generate_random_below(&q);
+        let k = ZKPUtils::generate_random_below(&q);
+        let below = &q - 1u32;
+        for convention in [
+            ResponseConvention::Subtractive,
+            ResponseConvention::Additive,
+        ] {
+            for precompute in [false, true] {
+                let mut builder = ZKPSystem::builder()
+                    .with_prime(p.clone())
+                    .with_order(q.clone())
+                    .with_generator(alpha.clone())
+                    .with_second_generator(beta.clone())
+                    .with_response_convention(convention);
+                if precompute {
+                    builder = builder.with_precomputation();
+                }
+                let system = builder.build().unwrap();
+                let verifier = Verifier::new(&system);
+                let (y1, y2) = system.compute_public_values(&x);
+                let (r1, r2) = system.compute_commitments(&k);
+
+                // q - 1 is the largest challenge in the space.
+                let s = system.compute_response(&k, &below, &x);
+                assert!(verifier.verify((&r1, &r2), &below, &s, (&y1, &y2)));
+                assert!(!verifier.verify((&r1, &r2), &BigUint::from(1u32), &s, (&y1, &y2)));
+
+                // q is reduced to 0, on both sides, and nothing else.
+                let zero = BigUint::from(0u32);
+                let s = system.compute_response(&k, &q, &x);
+                assert_eq!(s, system.compute_response(&k, &zero, &x));
+                assert!(verifier.verify((&r1, &r2), &q, &s, (&y1, &y2)));
+                assert!(verifier.verify((&r1, &r2), &zero, &s, (&y1, &y2)));
+                assert!(!verifier.verify((&r1, &r2), &below, &s, (&y1, &y2)));
+            }
+        }
+
+        // With an explicit bound, q is outside the challenge space.
+        let bounded = ZKPSystem::builder()
+            .with_prime(p)
+            .with_order(q.clone())
+            .with_generator(alpha)
+            .with_second_generator(beta)
+            .with_challenge_modulus(q.clone())
+            .build()
+            .unwrap();
+        let (y1, y2) = bounded.compute_public_values(&x);
+        let (r1, r2) = bounded.compute_commitments(&k);
+        let s = bounded.compute_response(&k, &below, &x);
+        assert!(bounded.verify((&r1, &r2), &below, &s, (&y1, &y2)));
+        let s = bounded.compute_response(&k, &q, &x);
+        assert!(!bounded.verify((&r1, &r2), &q, &s, (&y1, &y2)));
+    }
+
+    #[test]
+    fn test_modpow_multi_pooled_matches_unpooled() {
+        let (alpha, beta, p, q) = ZKPUtils::get_2048_bit_constants();
+        let small = BigUint::from(2039u32);
+        let y = ZKPUtils::generate_random_below(&p);
+        let c = ZKPUtils::generate_random_below(&q);
+
+        // Alternate term counts and moduli on one thread, so every call runs
+        // on a table left behind by a differently shaped one.
+        let cases: [(&[(&BigUint, &BigUint)], &BigUint); 5] = [
+            (&[(&alpha, &q), (&beta, &c), (&y, &c)], &p),
+            (&[(&alpha, &c)], &small),
+            (&[(&alpha, &c), (&y, &q)], &p),
+            (&[], &p),
+            (&[(&beta, &c), (&y, &c)], &small),
+        ];
+        for _ in 0..2 {
+            for (pairs, modulus) in cases {
+                assert_eq!(
+                    ZKPUtils::modpow_multi(pairs, modulus),
+                    ZKPUtils::modpow_multi_unpooled(pairs, modulus)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_error_split_converts_into_zkp_error() {
+        assert_eq!(
+            ZKPError::from(ProverError::SelfVerificationFailed),
+            ZKPError::SelfVerificationFailed
+        );
+        for (narrow, wide) in [
+            (VerifierError::SessionNotFound, ZKPError::SessionNotFound),
+            (VerifierError::UserNotFound, ZKPError::UserNotFound),
+            (
+                VerifierError::AuthenticationFailed,
+                ZKPError::AuthenticationFailed,
+            ),
+        ] {
+            assert_eq!(narrow.to_string(), wide.to_string());
+            assert_eq!(ZKPError::from(narrow), wide);
+        }
+
+        fn unified(e: ProverError) -> Result<(), ZKPError> {
+            Err(e)?
+        }
+        assert_eq!(
+            unified(ProverError::SelfVerificationFailed),
+            Err(ZKPError::SelfVerificationFailed)
+        );
+    }
+
+    #[test]
+    fn test_fixed_base_windows_match_modpow() {
+        let (alpha, _, p, q) = ZKPUtils::get_2048_bit_constants();
+        let exponents = [
+            BigUint::from(0u32),
+            BigUint::from(1u32),
+            &q - 1u32,
+            ZKPUtils::generate_random_below(&q),
+            // Longer than the table covers, so it takes the fallback path.
+            &p + 3u32,
+        ];
+        for window in 1..=8 {
+            let table = FixedBaseTable::with_window(&alpha, &p, q.bits(), window);
+            assert_eq!(table.window(), window);
+            for e in &exponents {
+                assert_eq!(table.pow(e), alpha.modpow(e, &p), "window {}", window);
+            }
+        }
+
+        assert_eq!(FixedBaseTable::window_for_modulus_bits(1024), 4);
+        assert_eq!(FixedBaseTable::window_for_modulus_bits(2048), 5);
+        assert_eq!(FixedBaseTable::window_for_modulus_bits(4096), 6);
+        assert_eq!(FixedBaseTable::new(&alpha, &p, q.bits()).window(), 5);
+    }
+
+    #[test]
+    fn test_precomputed_system() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let build = |builder: ZKPSystemBuilder| {
+            builder
+                .with_prime(p.clone())
+                .with_order(q.clone())
+                .with_generator(alpha.clone())
+                .with_second_generator(beta.clone())
+                .build()
+        };
+        let plain = build(ZKPSystem::builder()).unwrap();
+        let default = build(ZKPSystem::builder().with_precomputation()).unwrap();
+        let narrow = build(ZKPSystem::builder().with_precompute_window(2)).unwrap();
+        assert_eq!(plain.precompute_window(), None);
+        assert_eq!(default.precompute_window(), Some(4));
+        assert_eq!(narrow.precompute_window(), Some(2));
+        assert!(build(ZKPSystem::builder().with_precompute_window(0)).is_err());
+
+        let secret = ZKPUtils::generate_random_below(&q);
+        let k = ZKPUtils::generate_random_below(&q);
+        for system in [&default, &narrow] {
+            assert_eq!(
+                system.compute_public_values(&secret),
+                plain.compute_public_values(&secret)
+            );
+            assert_eq!(
+                system.compute_commitments(&k),
+                plain.compute_commitments(&k)
+            );
+
+            let prover = Prover::new(system, secret.clone());
+            let (commitments, randomness) = prover.generate_commitments();
+            let c = Verifier::new(system).generate_challenge();
+            let s = prover.generate_response(&c, &randomness);
+            let (y1, y2) = prover.public_values();
+            assert!(system.verify((&commitments.0, &commitments.1), &c, &s, (y1, y2)));
+            assert!(!system.verify((&commitments.0, &commitments.1), &(c + 1u32), &s, (y1, y2)));
+        }
+    }
+
+    #[test]
+    fn test_parameters_owned() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::builder()
+            .with_prime(p)
+            .with_order(q)
+            .with_generator(alpha)
+            .with_second_generator(beta)
+            .with_response_convention(ResponseConvention::Additive)
+            .build()
+            .unwrap();
+        let expected = system.parameters().clone();
+
+        let params = system.parameters_owned();
+        assert_eq!(params, expected);
+
+        let rebuilt = ZKPSystem::builder()
+            .with_prime(params.p.clone())
+            .with_order(params.q.clone())
+            .with_generator(params.alpha.clone())
+            .with_second_generator(params.beta.clone())
+            .with_response_convention(params.convention)
+            .build()
+            .unwrap();
+        assert_eq!(rebuilt.parameters(), &params);
+    }
+
+    #[test]
+    fn test_verify_stream_short_circuits() {
+        use std::cell::Cell;
+
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+        let prover = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+        let verifier = Verifier::new(&system);
+
+        let produced = Cell::new(0);
+        let proofs = (0..10).map(|i| {
+            produced.set(produced.get() + 1);
+            let (commitments, randomness) = prover.generate_commitments();
+            let challenge = verifier.generate_challenge();
+            let mut response = prover.generate_response(&challenge, &randomness);
+            if i == 2 {
+                response += 1u32;
+            }
+            ProofInput {
+                commitments,
+                challenge,
+                response,
+                public_values: prover.public_values_owned(),
+            }
+        });
+
+        let mut results = Vec::new();
+        for accepted in verifier.verify_stream(proofs) {
+            results.push(accepted);
+            if !accepted {
+                break;
+            }
+        }
+        assert_eq!(results, vec![true, true, false]);
+        assert_eq!(produced.get(), 3);
+    }
+
+    #[test]
+    fn test_min_modulus_bits() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let build = |min_bits| {
+            ZKPSystem::builder()
+                .with_prime(p.clone())
+                .with_order(q.clone())
+                .with_generator(alpha.clone())
+                .with_second_generator(beta.clone())
+                .with_min_modulus_bits(min_bits)
+                .build()
+        };
+
+        let err = build(2048).err().expect("1024-bit p is below 2048 bits");
+        assert_eq!(err, ZKPError::ModulusTooSmall);
+        assert!(build(1024).is_ok());
+        assert!(build(512).is_ok());
+    }
+
+    #[test]
+    fn test_build_with_config() {
+        let build = |p: u32, alpha: u32, config: &ZKPConfig| {
+            ZKPSystem::builder()
+                .with_prime(BigUint::from(p))
+                .with_order(BigUint::from(1019u32))
+                .with_generator(BigUint::from(alpha))
+                .with_second_generator(BigUint::from(9u32))
+                .with_config(config.clone())
+                .build()
+        };
+        let lenient = ZKPConfig::default();
+        let strict = ZKPConfig {
+            min_modulus_bits: Some(11),
+            primality_rounds: 8,
+            subgroup_checks: true,
+            validate_parameters: true,
+        };
+        assert!(build(2039, 4, &strict).is_ok());
+
+        // 2038 = -1 has order 2; 4077 = 3 * 1359 with 1019 | 4076.
+        assert!(build(2039, 2038, &lenient).is_ok());
+        let err = build(2039, 2038, &strict).err().unwrap();
+        assert_eq!(err, ZKPError::GeneratorNotInSubgroup);
+        assert!(build(4077, 4, &lenient).is_ok());
+        let err = build(4077, 4, &strict).err().unwrap();
+        assert_eq!(err, ZKPError::InvalidParameters);
+
+        let too_short = ZKPConfig {
+            min_modulus_bits: Some(12),
+            ..strict.clone()
+        };
+        let err = build(2039, 4, &too_short).err().unwrap();
+        assert_eq!(err, ZKPError::ModulusTooSmall);
+
+        // A later config replaces an earlier minimum size.
+        let relaxed = ZKPSystem::builder()
+            .with_prime(BigUint::from(2039u32))
+            .with_generator(BigUint::from(4u32))
+            .with_second_generator(BigUint::from(9u32))
+            .with_min_modulus_bits(2048)
+            .with_config(strict)
+            .build();
+        assert_eq!(relaxed.unwrap().parameters().q, BigUint::from(1019u32));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_config_serde_defaults_missing_fields() {
+        let config: ZKPConfig = serde_json::from_str(r#"{"subgroup_checks":true}"#).unwrap();
+        assert_eq!(
+            config,
+            ZKPConfig {
+                subgroup_checks: true,
+                ..ZKPConfig::default()
+            }
+        );
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(serde_json::from_str::<ZKPConfig>(&json).unwrap(), config);
+    }
+
+    #[test]
+    fn test_group_arithmetic() {
+        let group = Group::new(BigUint::from(2039u32), BigUint::from(1019u32));
+        let n = |v: u32| BigUint::from(v);
+        assert_eq!(group.modulus(), &n(2039));
+        assert_eq!(group.order(), &n(1019));
+
+        assert_eq!(group.pow(&n(2), &n(11)), n(2048 - 2039));
+        assert_eq!(group.pow(&n(4), &n(0)), n(1));
+        assert_eq!(group.pow(&n(4), &n(1019)), n(1));
+        assert_eq!(group.mul(&n(2038), &n(2038)), n(1));
+        assert_eq!(group.mul(&n(100), &n(30)), n(3000 - 2039));
+        assert_eq!(group.reduce(&n(5000)), n(5000 - 2 * 2039));
+        assert_eq!(group.reduce_exponent(&n(2040)), n(2));
+
+        // g^a * g^b == g^(a + b), and exponents only matter modulo q.
+        let (a, b) = (n(700), n(900));
+        assert_eq!(
+            group.mul(&group.pow(&n(9), &a), &group.pow(&n(9), &b)),
+            group.pow(&n(9), &group.reduce_exponent(&(&a + &b)))
+        );
+
+        assert!(group.subgroup_contains(&n(4)));
+        assert!(group.subgroup_contains(&n(1)));
+        assert!(!group.subgroup_contains(&n(2038)));
+        assert!(!group.subgroup_contains(&n(0)));
+        assert!(!group.subgroup_contains(&n(2039 + 4)));
+
+        let params = ZKPParameters::from((n(4), n(9), n(2039), n(1019)));
+        assert_eq!(params.group(), group);
+    }
+
+    #[test]
+    fn test_parameters_writer_reader_round_trip() {
+        use std::io::{Cursor, ErrorKind};
+
+        let plain = ZKPParameters::from(ZKPUtils::get_1024_bit_constants());
+        let mut tuned = plain.clone();
+        tuned.convention = ResponseConvention::Additive;
+        tuned.c_max = Some(BigUint::from(1u32) << 64);
+
+        // Both in one stream: each read consumes exactly one encoding.
+        let mut buffer = Cursor::new(Vec::new());
+        plain.to_writer(&mut buffer).unwrap();
+        tuned.to_writer(&mut buffer).unwrap();
+        let bytes = buffer.into_inner();
+        let mut reader = Cursor::new(&bytes);
+        assert_eq!(ZKPParameters::from_reader(&mut reader).unwrap(), plain);
+        assert_eq!(ZKPParameters::from_reader(&mut reader).unwrap(), tuned);
+        assert_eq!(reader.position() as usize, bytes.len());
+
+        let mut encoded = Vec::new();
+        plain.to_writer(&mut encoded).unwrap();
+        let truncated = &encoded[..encoded.len() - 1];
+        assert_eq!(
+            ZKPParameters::from_reader(truncated).unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+        let invalid_data = |bytes: &[u8]| {
+            let err = ZKPParameters::from_reader(bytes).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+            err.into_inner()
+                .and_then(|e| e.downcast::<ZKPError>().ok())
+                .map(|e| *e)
+        };
+        let mut bad_version = encoded.clone();
+        bad_version[0] = 9;
+        assert_eq!(
+            invalid_data(&bad_version),
+            Some(ZKPError::UnsupportedParametersVersion(9))
+        );
+        // The byte after the four values is the convention tag.
+        let mut bad_convention = encoded.clone();
+        let tag = encoded.len() - 2;
+        bad_convention[tag] = 7;
+        assert_eq!(
+            invalid_data(&bad_convention),
+            Some(ZKPError::MalformedParameters)
+        );
+        // A length far beyond the input is reported, not allocated.
+        let forged = [&[ZKPParameters::ENCODING_VERSION][..], &[0xff; 4], &[1, 2]].concat();
+        assert_eq!(
+            ZKPParameters::from_reader(&forged[..]).unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn test_random_subgroup_element() {
+        let params = ZKPParameters::from(ZKPUtils::get_1024_bit_constants());
+        let first = params.random_subgroup_element();
+        assert!(params.subgroup_contains(&first));
+        assert!(params.subgroup_contains(&params.random_subgroup_element()));
+        assert_ne!(first, params.random_subgroup_element());
+    }
+
+    #[test]
+    fn test_parameters_from_constants_tuple() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let params = ZKPParameters::from(ZKPUtils::get_1024_bit_constants());
+        assert_eq!(
+            (&params.p, &params.q, &params.alpha, &params.beta),
+            (&p, &q, &alpha, &beta)
+        );
+
+        let secret = ZKPUtils::generate_random_below(&params.q);
+        let k = ZKPUtils::generate_random_below(&params.q);
+        let c = ZKPUtils::generate_random_below(&params.q);
+        let (y1, y2) = params.compute_public_keys(&secret);
+        let (r1, r2) = params.compute_commitments(&k);
+        let s = params.compute_response(&k, &c, &secret);
+        assert!(params.verify((&r1, &r2), &c, &s, (&y1, &y2)));
+    }
+
+    #[test]
+    fn test_seal_secret_round_trip() {
+        let (_, _, _, q) = ZKPUtils::get_1024_bit_constants();
+        let secret = ZKPUtils::generate_random_below(&q);
+
+        let sealed = ZKPUtils::seal_secret(&secret, "correct horse");
+        assert!(!sealed
+            .windows(secret.to_bytes_be().len())
+            .any(|w| w == secret.to_bytes_be().as_slice()));
+        assert_eq!(ZKPUtils::open_secret(&sealed, "correct horse"), Ok(secret));
+
+        assert_eq!(
+            ZKPUtils::open_secret(&sealed, "wrong horse"),
+            Err(ZKPError::SecretUnsealFailed)
+        );
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            ZKPUtils::open_secret(&tampered, "correct horse"),
+            Err(ZKPError::SecretUnsealFailed)
+        );
+        assert_eq!(
+            ZKPUtils::open_secret(&sealed[..10], "correct horse"),
+            Err(ZKPError::SecretUnsealFailed)
+        );
+    }
+
+    #[test]
+    fn test_hash_domain_binds_non_interactive_proofs() {
+        let small = ZKPParameters::from(ZKPUtils::get_1024_bit_constants());
+        let large = ZKPParameters::from(ZKPUtils::get_2048_bit_constants());
+        assert_eq!(small.hash_domain().len(), 32);
+        assert_eq!(small.hash_domain(), small.clone().hash_domain());
+        assert_ne!(small.hash_domain(), large.hash_domain());
+
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+        let prover = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+        let proof = prover.prove_non_interactive();
+        let (y1, y2) = prover.public_values();
+        let (r1, r2) = &proof.commitments;
+
+        let bound = ZKPUtils::fiat_shamir_challenge_in_domain::<Sha256>(
+            system.get_order(),
+            &system.parameters().hash_domain(),
+            &[y1, y2, r1, r2],
+        );
+        let unbound =
+            ZKPUtils::fiat_shamir_challenge::<Sha256>(system.get_order(), &[y1, y2, r1, r2]);
+        assert!(system.verify((r1, r2), &bound, &proof.response, (y1, y2)));
+        assert!(!system.verify((r1, r2), &unbound, &proof.response, (y1, y2)));
+        assert!(Verifier::new(&system).verify_non_interactive(&proof, (y1, y2)));
+    }
+
+    #[test]
+    fn test_challenge_bytes() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q.clone(), alpha, beta);
+        let prover = Prover::new(&system, ZKPUtils::generate_random_below(&q));
+        let verifier = Verifier::new(&system);
+        let (y1, y2) = prover.public_values();
+
+        let (commitments, randomness) = prover.generate_commitments();
+        let challenge = verifier.generate_challenge();
+        let bytes = challenge.to_bytes_be();
+        let response = prover.generate_response_bytes(&bytes, &randomness).unwrap();
+        assert_eq!(response, prover.generate_response(&challenge, &randomness));
+        assert_eq!(
+            verifier.verify_bytes(
+                (&commitments.0, &commitments.1),
+                &bytes,
+                &response,
+                (y1, y2)
+            ),
+            Ok(true)
+        );
+
+        let over_range = (&q + 1u32).to_bytes_be();
+        assert_eq!(
+            prover.generate_response_bytes(&over_range, &randomness),
+            Err(ZKPError::ChallengeOutOfRange)
+        );
+        assert_eq!(
+            verifier.verify_bytes(
+                (&commitments.0, &commitments.1),
+                &q.to_bytes_be(),
+                &response,
+                (y1, y2)
+            ),
+            Err(ZKPError::ChallengeOutOfRange)
+        );
+    }
+
+    #[test]
+    fn test_verifiers_share_precomputed_system() {
+        use std::sync::Arc;
+
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = Arc::new(
+            ZKPSystem::builder()
+                .with_prime(p)
+                .with_order(q)
+                .with_generator(alpha)
+                .with_second_generator(beta)
+                .with_precomputation()
+                .build()
+                .unwrap(),
+        );
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let system = Arc::clone(&system);
+                std::thread::spawn(move || {
+                    let prover = Prover::new(
+                        &*system,
+                        ZKPUtils::generate_random_below(system.get_order()),
+                    );
+                    let verifier = Verifier::new(&*system);
+                    let (commitments, randomness) = prover.generate_commitments();
+                    let c = verifier.generate_challenge();
+                    let s = prover.generate_response(&c, &randomness);
+                    let (y1, y2) = prover.public_values();
+                    verifier.verify((&commitments.0, &commitments.1), &c, &s, (y1, y2))
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            assert!(handle.join().unwrap());
+        }
+        assert_eq!(system.precompute_window(), Some(4));
+    }
+
+    #[test]
+    fn test_generate_parameters() {
+        let params = ZKPUtils::generate_parameters(64);
+        assert_eq!(params.p.bits(), 64);
+        assert_eq!(params.p, &params.q * 2u32 + 1u32);
+        assert!(ZKPUtils::is_probable_prime(&params.p, 40));
+        assert!(ZKPUtils::is_probable_prime(&params.q, 40));
+        assert!(params.subgroup_contains(&params.alpha));
+        assert!(params.subgroup_contains(&params.beta));
+        assert_ne!(params.alpha, BigUint::from(1u32));
+        assert_ne!(params.alpha, params.beta);
+    }
+
+    #[test]
+    fn test_generate_parameters_with_seeded_rng() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let first = ZKPUtils::generate_parameters_with_rng(64, &mut StdRng::seed_from_u64(7));
+        let second = ZKPUtils::generate_parameters_with_rng(64, &mut StdRng::seed_from_u64(7));
+        assert_eq!(first, second);
+        assert_eq!(first.p, &first.q * 2u32 + 1u32);
+        assert!(ZKPUtils::is_probable_prime(&first.p, 40));
+        assert!(first.subgroup_contains(&first.beta));
+
+        let other = ZKPUtils::generate_parameters_with_rng(64, &mut StdRng::seed_from_u64(8));
+        assert_ne!(first.p, other.p);
+    }
+
+    #[test]
+    fn test_verify_batch_with_repeated_challenges() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+        let verifier = Verifier::new(&system);
+
+        let mut proofs = Vec::new();
+        for _ in 0..3 {
+            let prover = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+            let challenge = verifier.generate_challenge();
+            // Several sessions of the same user answering the same challenge.
+            for _ in 0..3 {
+                let (commitments, randomness) = prover.generate_commitments();
+                proofs.push(ProofInput {
+                    response: prover.generate_response(&challenge, &randomness),
+                    commitments,
+                    challenge: challenge.clone(),
+                    public_values: prover.public_values_owned(),
+                });
+            }
+        }
+        proofs[4].response += 1u32;
+
+        let expected: Vec<bool> = (0..proofs.len()).map(|i| i != 4).collect();
+        assert_eq!(verifier.verify_batch(&proofs), expected);
+        let one_by_one: Vec<bool> = verifier.verify_stream(proofs.iter().cloned()).collect();
+        assert_eq!(one_by_one, expected);
+    }
+
+    #[test]
+    fn test_prover_debug_redacts_secret() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+        let secret = ZKPUtils::generate_random_below(system.get_order());
+        let prover = Prover::new(&system, secret.clone());
+
+        let debug = format!("{:?}", prover);
+        assert!(debug.contains("secret: <redacted>"));
+        assert!(!debug.contains(&secret.to_string()));
+        assert!(debug.contains("public_values: None"));
+
+        let (y1, _) = prover.public_values();
+        let debug = format!("{:?}", prover);
+        assert!(!debug.contains(&secret.to_string()));
+        assert!(debug.contains(&y1.to_string()));
+    }
+
+    #[test]
+    fn test_self_test_passes_on_shipped_constants() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let plain = ZKPSystem::new(p.clone(), q.clone(), alpha.clone(), beta.clone());
+        assert_eq!(plain.self_test(), Ok(()));
+
+        let precomputed = ZKPSystem::builder()
+            .with_prime(p)
+            .with_order(q)
+            .with_generator(alpha)
+            .with_second_generator(beta)
+            .with_precomputation()
+            .build()
+            .unwrap();
+        assert_eq!(precomputed.self_test(), Ok(()));
+
+        // A beta outside the subgroup breaks the honest proof.
+        let (alpha, _, p, q) = ZKPUtils::get_1024_bit_constants();
+        let broken = ZKPSystem::new(p.clone(), q, alpha, p - 1u32);
+        assert!(matches!(
+            broken.self_test(),
+            Err(ZKPError::SelfTestFailed(_))
+        ));
+    }
+
+    struct OwnedParties {
+        prover: Prover<'static, ZKPSystem>,
+        verifier: Verifier<'static, ZKPSystem>,
+    }
+
+    fn make_owned_parties() -> OwnedParties {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let secret = ZKPUtils::generate_random_below(&q);
+        OwnedParties {
+            prover: Prover::new_owned(
+                ZKPSystem::new(p.clone(), q.clone(), alpha.clone(), beta.clone()),
+                secret,
+            ),
+            verifier: Verifier::new_owned(ZKPSystem::new(p, q, alpha, beta)),
+        }
+    }
+
+    fn run_owned_parties(parties: &OwnedParties) -> bool {
+        let (commitments, randomness) = parties.prover.generate_commitments();
+        let challenge = parties.verifier.generate_challenge();
+        let response = parties.prover.generate_response(&challenge, &randomness);
+        let (y1, y2) = parties.prover.public_values();
+        parties.verifier.verify(
+            (&commitments.0, &commitments.1),
+            &challenge,
+            &response,
+            (y1, y2),
+        )
+    }
+
+    #[test]
+    fn test_owned_prover_and_verifier_in_struct() {
+        let parties = make_owned_parties();
+        assert!(run_owned_parties(&parties));
+    }
+
+    #[test]
+    fn test_decode_canonical() {
+        let value = BigUint::from(0x0105u32);
+        assert_eq!(ZKPUtils::canonical_bytes(&value, 2), Some(vec![0x01, 0x05]));
+        assert_eq!(ZKPUtils::canonical_bytes(&value, 1), None);
+        assert_eq!(ZKPUtils::decode_canonical(&[0x01, 0x05], 2), Ok(value));
+        assert_eq!(
+            ZKPUtils::decode_canonical(&[0x00], 2),
+            Ok(BigUint::from(0u32))
+        );
+
+        for bytes in [
+            &[0x00, 0x05][..],
+            &[0x00, 0x00][..],
+            &[][..],
+            &[0x01, 0x00, 0x05][..],
+        ] {
+            assert_eq!(
+                ZKPUtils::decode_canonical(bytes, 2),
+                Err(ZKPError::NonCanonicalEncoding)
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify_any_returns_matching_candidate() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+        let old = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+        let new = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+        let verifier = Verifier::new(&system);
+        let candidates = [old.public_values_owned(), new.public_values_owned()];
+
+        let ((r1, r2), randomness) = new.generate_commitments();
+        let challenge = verifier.generate_challenge();
+        let response = new.generate_response(&challenge, &randomness);
+        assert_eq!(
+            verifier.verify_any((&r1, &r2), &challenge, &response, &candidates),
+            Some(1)
+        );
+        assert_eq!(
+            verifier.verify_any((&r1, &r2), &challenge, &response, &candidates[..1]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_registration_record_metadata_is_preserved() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = Arc::new(ZKPSystem::new(p, q, alpha, beta));
+        let service = AuthService::new(system.clone());
+        let prover = Prover::new(
+            &*system,
+            ZKPUtils::generate_random_below(system.get_order()),
+        );
+        let (y1, y2) = prover.public_values_owned();
+
+        let record = RegistrationRecord::new(y1.clone(), y2.clone()).with_label("laptop");
+        service.register_record("alice", record.clone()).unwrap();
+        assert_eq!(service.record("alice"), Some(record.clone()));
+        assert_eq!(
+            service.public_values("alice"),
+            Some((y1.clone(), y2.clone()))
+        );
+        assert_eq!(service.record("bob"), None);
+
+        // Authentication only looks at (y1, y2).
+        let ((r1, r2), randomness) = prover.generate_commitments();
+        let (auth_id, challenge) = service.create_challenge("alice", r1, r2).unwrap();
+        let s = prover.generate_response(&challenge, &randomness);
+        assert!(service.verify(&auth_id, &s).is_ok());
+
+        // The record also comes back through the store trait.
+        fn stored<S: PublicValueStore>(store: &S, user: &str) -> Option<RegistrationRecord> {
+            store.record(user)
+        }
+        assert_eq!(stored(&service, "alice"), Some(record.clone()));
+        assert_eq!(stored(&service, "bob"), None);
+
+        let store: HashMap<String, RegistrationRecord> =
+            HashMap::from([("alice".to_string(), record.clone())]);
+        assert_eq!(store.public_values("alice"), Some((y1.clone(), y2.clone())));
+        assert_eq!(stored(&store, "alice"), Some(record));
+
+        // A store of bare public values keeps no record.
+        let bare: HashMap<String, (BigUint, BigUint)> =
+            HashMap::from([("alice".to_string(), (y1, y2))]);
+        assert_eq!(stored(&bare, "alice"), None);
+    }
+
+    #[test]
+    fn test_sign_and_verify_signature() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+        let prover = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+        let verifier = Verifier::new(&system);
+        let (y1, y2) = prover.public_values();
+
+        let signature = prover.sign(b"transfer 10 to bob");
+        assert!(verifier.verify_signature(b"transfer 10 to bob", &signature, (y1, y2)));
+        assert!(!verifier.verify_signature(b"transfer 99 to bob", &signature, (y1, y2)));
+
+        let other = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+        let (o1, o2) = other.public_values();
+        assert!(!verifier.verify_signature(b"transfer 10 to bob", &signature, (o1, o2)));
+    }
+
+    #[test]
+    fn test_builder_rejects_identical_generators() {
+        let (alpha, _, p, q) = ZKPUtils::get_1024_bit_constants();
+        let err = ZKPSystem::builder()
+            .with_prime(p)
+            .with_order(q)
+            .with_generator(alpha.clone())
+            .with_second_generator(alpha)
+            .build()
+            .err()
+            .expect("identical generators must be rejected");
+        assert_eq!(err, ZKPError::GeneratorsNotDistinct);
+    }
+
+    #[test]
+    fn test_cofactor() {
+        let safe = ZKPParameters::from((
+            BigUint::from(4u32),
+            BigUint::from(9u32),
+            BigUint::from(2039u32),
+            BigUint::from(1019u32),
+        ));
+        assert_eq!(safe.cofactor(), Ok(BigUint::from(2u32)));
+
+        let params = ZKPParameters::from(ZKPUtils::get_1024_bit_constants());
+        let h = params.cofactor().unwrap();
+        assert!(h > BigUint::from(2u32));
+        assert_eq!(h * &params.q, &params.p - 1u32);
+
+        let broken = ZKPParameters::from((
+            BigUint::from(4u32),
+            BigUint::from(9u32),
+            BigUint::from(2039u32),
+            BigUint::from(7u32),
+        ));
+        assert_eq!(broken.cofactor(), Err(ZKPError::OrderDoesNotDivide));
+    }
+
+    /// Delegates to a `ZKPSystem` but keeps the trait's default `verify_batch`.
+    struct DefaultBatch<'a>(&'a ZKPSystem);
+
+    impl ZKPProtocol for DefaultBatch<'_> {
+        fn compute_commitments(&self, randomness: &BigUint) -> (BigUint, BigUint) {
+            self.0.compute_commitments(randomness)
+        }
+
+        fn compute_response(
+            &self,
+            randomness: &BigUint,
+            challenge: &BigUint,
+            secret: &BigUint,
+        ) -> BigUint {
+            self.0.compute_response(randomness, challenge, secret)
+        }
+
+        fn verify(
+            &self,
+            commitments: (&BigUint, &BigUint),
+            challenge: &BigUint,
+            response: &BigUint,
+            public_values: (&BigUint, &BigUint),
+        ) -> bool {
+            self.0
+                .verify(commitments, challenge, response, public_values)
+        }
+
+        fn compute_public_values(&self, secret: &BigUint) -> (BigUint, BigUint) {
+            self.0.compute_public_values(secret)
+        }
+
+        fn get_order(&self) -> &BigUint {
+            self.0.get_order()
+        }
+    }
+
+    #[test]
+    fn test_default_and_optimized_verify_batch_agree() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+        let verifier = Verifier::new(&system);
+        let mut proofs: Vec<ProofInput> = (0..4)
+            .map(|_| {
+                let prover =
+                    Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+                let (commitments, randomness) = prover.generate_commitments();
+                let challenge = verifier.generate_challenge();
+                ProofInput {
+                    response: prover.generate_response(&challenge, &randomness),
+                    commitments,
+                    challenge,
+                    public_values: prover.public_values_owned(),
+                }
+            })
+            .collect();
+
+        let default = DefaultBatch(&system);
+        assert_eq!(default.verify_batch(&proofs), vec![true; 4]);
+        assert_eq!(system.verify_batch(&proofs), vec![true; 4]);
+        assert!(system.parameters().verify_batch_combined(&proofs));
+
+        proofs[2].response += 1u32;
+        let expected = vec![true, true, false, true];
+        assert_eq!(default.verify_batch(&proofs), expected);
+        assert_eq!(system.verify_batch(&proofs), expected);
+        assert!(!system.parameters().verify_batch_combined(&proofs));
+    }
+
+    #[test]
+    fn test_builder_from_changes_only_beta() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let original = ZKPSystem::new(p, q, alpha, beta);
+        let new_beta = original.parameters().derive_new_beta(b"rotation-1");
+
+        let system = ZKPSystem::builder_from(original.parameters())
+            .with_second_generator(new_beta.clone())
+            .build()
+            .unwrap();
+        let (old, new) = (original.parameters(), system.parameters());
+        assert_eq!((&new.p, &new.q, &new.alpha), (&old.p, &old.q, &old.alpha));
+        assert_eq!(new.beta, new_beta);
+        assert_ne!(new.beta, old.beta);
+
+        let prover = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+        let verifier = Verifier::new(&system);
+        let ((r1, r2), randomness) = prover.generate_commitments();
+        let challenge = verifier.generate_challenge();
+        let s = prover.generate_response(&challenge, &randomness);
+        let (y1, y2) = prover.public_values();
+        assert!(verifier.verify((&r1, &r2), &challenge, &s, (y1, y2)));
+    }
+
+    fn counter_generator() -> impl FnMut() -> BigUint {
+        let mut next = 0u32;
+        move || {
+            next += 1;
+            BigUint::from(next)
+        }
+    }
+
+    fn commitments() -> (BigUint, BigUint) {
+        (BigUint::from(2u32), BigUint::from(3u32))
+    }
+
+    fn public_values() -> (BigUint, BigUint) {
+        (BigUint::from(5u32), BigUint::from(7u32))
+    }
+
+    #[test]
+    fn test_session_manager_create_and_consume_once() {
+        let sessions = SessionManager::new();
+        let (auth_id, challenge) = sessions
+            .create("alice", commitments(), public_values(), counter_generator())
+            .unwrap();
+        assert_eq!(sessions.len(), 1);
+
+        let pending = sessions.consume(&auth_id).unwrap();
+        assert_eq!(pending.user, "alice");
+        assert_eq!(pending.challenge, challenge);
+        assert_eq!(pending.commitments, commitments());
+        assert_eq!(pending.public_values, public_values());
+
+        assert_eq!(sessions.consume(&auth_id), None);
+        assert!(sessions.is_empty());
+    }
+
+    #[test]
+    fn test_session_manager_skips_live_challenges_of_same_user() {
+        let sessions = SessionManager::new();
+        let constant_then_counter = {
+            let mut counter = counter_generator();
+            let mut calls = 0;
+            move || {
+                calls += 1;
+                if calls <= 2 {
+                    BigUint::from(7u32)
+                } else {
+                    counter()
+                }
+            }
+        };
+        let mut generate = constant_then_counter;
+        let (_, first) = sessions
+            .create("alice", commitments(), public_values(), &mut generate)
+            .unwrap();
+        let (_, second) = sessions
+            .create("alice", commitments(), public_values(), &mut generate)
+            .unwrap();
+        assert_eq!(first, BigUint::from(7u32));
+        assert_ne!(second, first);
+    }
+
+    #[test]
+    fn test_session_manager_expiry_and_gc() {
+        let sessions = SessionManager::with_ttl(Duration::ZERO);
+        let (expired, _) = sessions
+            .create("alice", commitments(), public_values(), counter_generator())
+            .unwrap();
+        assert_eq!(sessions.consume(&expired), None);
+
+        sessions
+            .create("alice", commitments(), public_values(), counter_generator())
+            .unwrap();
+        sessions
+            .create("bob", commitments(), public_values(), counter_generator())
+            .unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions.gc(), 2);
+        assert!(sessions.is_empty());
+
+        let sessions = SessionManager::with_ttl(Duration::from_secs(3600));
+        let (live, _) = sessions
+            .create("alice", commitments(), public_values(), counter_generator())
+            .unwrap();
+        assert_eq!(sessions.gc(), 0);
+        assert!(sessions.consume(&live).is_some());
+    }
+
+    #[test]
+    fn test_non_interactive_agrees_with_interactive_on_derived_challenge() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+        let prover = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+        let verifier = Verifier::new(&system);
+        let (y1, y2) = prover.public_values();
+
+        let valid = prover.prove_non_interactive();
+        let mut tampered = valid.clone();
+        tampered.response += 1u32;
+
+        for (proof, expected) in [(valid, true), (tampered, false)] {
+            let challenge = verifier.non_interactive_challenge_with::<Sha256>(&proof, (y1, y2));
+            let (r1, r2) = &proof.commitments;
+            let interactive = verifier.verify((r1, r2), &challenge, &proof.response, (y1, y2));
+            let non_interactive = verifier.verify_non_interactive(&proof, (y1, y2));
+            assert_eq!(interactive, expected);
+            assert_eq!(non_interactive, expected);
+        }
+    }
+
+    #[test]
+    fn test_challenge_entropy_bits() {
+        let toy = ZKPSystem::new(
+            BigUint::from(2039u32),
+            BigUint::from(1019u32),
+            BigUint::from(4u32),
+            BigUint::from(9u32),
+        );
+        let verifier = Verifier::new(&toy);
+        assert_eq!(verifier.challenge_entropy_bits(), 9);
+        assert!(
+            verifier.challenge_entropy_bits() < Verifier::<ZKPSystem>::MIN_CHALLENGE_ENTROPY_BITS
+        );
+
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+        let verifier = Verifier::new(&system);
+        assert!(
+            verifier.challenge_entropy_bits() >= Verifier::<ZKPSystem>::MIN_CHALLENGE_ENTROPY_BITS
+        );
+    }
+
+    fn sample_transcript() -> Transcript {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+        let prover = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+        let (commitments, randomness) = prover.generate_commitments();
+        let challenge = Verifier::new(&system).generate_challenge();
+        Transcript {
+            public_values: prover.public_values_owned(),
+            response: prover.generate_response(&challenge, &randomness),
+            commitments,
+            challenge,
+        }
+    }
+
+    #[test]
+    fn test_transcript_bytes_round_trip() {
+        let transcript = sample_transcript();
+        let bytes = transcript.to_bytes();
+        assert_eq!(Transcript::from_bytes(&bytes), Ok(transcript));
+        assert_eq!(
+            Transcript::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ZKPError::MalformedProof)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_transcript_serde_representations() {
+        use transcript::repr::{Compact, Verbose};
+
+        let transcript = sample_transcript();
+
+        let verbose = serde_json::to_string(&Verbose(transcript.clone())).unwrap();
+        assert!(verbose.contains(&format!("\"c\":\"{}\"", transcript.challenge)));
+        let parsed: Verbose = serde_json::from_str(&verbose).unwrap();
+        assert_eq!(parsed.0, transcript);
+
+        let compact = serde_json::to_string(&Compact(transcript.clone())).unwrap();
+        assert!(compact.len() < verbose.len());
+        let parsed: Compact = serde_json::from_str(&compact).unwrap();
+        assert_eq!(parsed.0, transcript);
+
+        // Read one representation, write the other.
+        let from_verbose: Verbose = serde_json::from_str(&verbose).unwrap();
+        assert_eq!(
+            serde_json::to_string(&Compact::from(from_verbose)).unwrap(),
+            compact
+        );
+        let from_compact: Compact = serde_json::from_str(&compact).unwrap();
+        assert_eq!(
+            serde_json::to_string(&Verbose::from(from_compact)).unwrap(),
+            verbose
+        );
+    }
+
+    #[test]
+    fn test_batch_commit() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+        let prover = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+        let verifier = Verifier::new(&system);
+        let (y1, y2) = prover.public_values();
+
+        let batch = prover.batch_commit(8);
+        assert_eq!(batch.len(), 8);
+        let nonces: std::collections::HashSet<_> = batch.iter().map(|(_, k)| k).collect();
+        assert_eq!(nonces.len(), 8);
+
+        for ((r1, r2), randomness) in &batch {
+            let challenge = verifier.generate_challenge();
+            let s = prover.generate_response(&challenge, randomness);
+            assert!(verifier.verify((r1, r2), &challenge, &s, (y1, y2)));
+        }
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn test_prometheus_metrics_after_operations() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = Arc::new(ZKPSystem::new(p, q, alpha, beta));
+        let metrics = Arc::new(PrometheusMetrics::new());
+        let service = AuthService::new(system.clone()).with_metrics(metrics.clone());
+        let prover = Prover::new(
+            &*system,
+            ZKPUtils::generate_random_below(system.get_order()),
+        );
+        let (y1, y2) = prover.public_values_owned();
+        service.register("alice", y1, y2).unwrap();
+
+        for tamper in [false, true] {
+            let ((r1, r2), randomness) = prover.generate_commitments();
+            let (auth_id, challenge) = service.create_challenge("alice", r1, r2).unwrap();
+            let mut s = prover.generate_response(&challenge, &randomness);
+            if tamper {
+                s += 1u32;
+            }
+            assert_eq!(service.verify(&auth_id, &s).is_ok(), !tamper);
+        }
+
+        let rendered = metrics.render();
+        for line in [
+            "auth_registrations_total 1",
+            "auth_challenges_total 2",
+            "auth_verifications_total{outcome=\"success\"} 1",
+            "auth_verifications_total{outcome=\"failure\"} 1",
+            "auth_verify_latency_seconds_bucket{le=\"+Inf\"} 2",
+            "auth_verify_latency_seconds_count 2",
+        ] {
+            assert!(
+                rendered.lines().any(|l| l == line),
+                "missing `{}` in\n{}",
+                line,
+                rendered
+            );
+        }
+    }
+
+    #[test]
+    fn test_reduce_matches_modpow_one() {
+        let params = ZKPParameters::from(ZKPUtils::get_1024_bit_constants());
+        let one = BigUint::from(1u32);
+        let wide = &params.p * &params.p + 12345u32;
+        for v in [
+            BigUint::from(0u32),
+            params.q.clone(),
+            &params.p - 1u32,
+            wide,
+        ] {
+            assert_eq!(params.reduce_p(&v), v.modpow(&one, &params.p));
+            assert_eq!(params.reduce_q(&v), v.modpow(&one, &params.q));
+        }
+    }
+
+    #[test]
+    fn test_verifier_subgroup_checks() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p.clone(), q, alpha, beta);
+        let prover = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+        let checked = Verifier::new(&system);
+        let unchecked = Verifier::new(&system).with_subgroup_checks(false);
+        let (y1, y2) = prover.public_values();
+        let ((r1, r2), k) = prover.generate_commitments();
+        // an odd challenge keeps (-1)^c = -1, so negating r1 and y1 cancels out
+        let c = checked.generate_challenge() | BigUint::from(1u32);
+        let s = prover.generate_response(&c, &k);
+
+        for verifier in [&checked, &unchecked] {
+            assert!(verifier.verify((&r1, &r2), &c, &s, (y1, y2)));
+        }
+
+        let neg_r1 = &p - &r1;
+        let neg_y1 = &p - y1;
+        assert!(!system.parameters().subgroup_contains(&neg_r1));
+        assert!(unchecked.verify((&neg_r1, &r2), &c, &s, (&neg_y1, y2)));
+        assert!(!checked.verify((&neg_r1, &r2), &c, &s, (&neg_y1, y2)));
+    }
+
+    #[test]
+    fn test_prover_change_secret() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+        let mut prover = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+        let verifier = Verifier::new(&system);
+        let (old_y1, old_y2) = prover.public_values().clone();
+
+        let new_secret = ZKPUtils::generate_random_below(system.get_order());
+        prover.change_secret(new_secret.clone());
+        assert_eq!(
+            *prover.public_values(),
+            system.compute_public_values(&new_secret)
+        );
+
+        let ((r1, r2), k) = prover.generate_commitments();
+        let c = verifier.generate_challenge();
+        let s = prover.generate_response(&c, &k);
+        let (y1, y2) = prover.public_values();
+        assert!(verifier.verify((&r1, &r2), &c, &s, (y1, y2)));
+        assert!(!verifier.verify((&r1, &r2), &c, &s, (&old_y1, &old_y2)));
+    }
+
+    #[test]
+    fn test_prover_reduces_secret_above_order() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+        let secret = ZKPUtils::generate_random_below(system.get_order());
+        let large = &secret + system.get_order() * 3u32;
+        let prover = Prover::new(&system, large.clone());
+        let verifier = Verifier::new(&system);
+
+        assert_eq!(
+            *prover.public_values(),
+            system.compute_public_values(&secret)
+        );
+        assert_eq!(
+            *prover.public_values(),
+            system.compute_public_values(&large)
+        );
+
+        let ((r1, r2), k) = prover.generate_commitments();
+        let c = verifier.generate_challenge();
+        let s = prover.generate_response(&c, &k);
+        assert!(s < *system.get_order());
+        let (y1, y2) = prover.public_values();
+        assert!(verifier.verify((&r1, &r2), &c, &s, (y1, y2)));
+    }
+
+    #[test]
+    fn test_builder_parses_string_parameters() {
+        let hex = ZKPSystem::builder()
+            .with_prime_str("0x7F7")
+            .with_order_str("0x3fb")
+            .with_generator_str("0x4")
+            .with_second_generator_str("0x9")
+            .build()
+            .unwrap();
+        let decimal = ZKPSystem::builder()
+            .with_prime_str("2039")
+            .with_order_str("1019")
+            .with_generator_str("4")
+            .with_second_generator_str("9")
+            .build()
+            .unwrap();
+        for system in [&hex, &decimal] {
+            let params = system.parameters();
+            assert_eq!(params.p, BigUint::from(2039u32));
+            assert_eq!(params.q, BigUint::from(1019u32));
+            assert_eq!(params.alpha, BigUint::from(4u32));
+            assert_eq!(params.beta, BigUint::from(9u32));
+        }
+
+        for malformed in ["", "0x", "-5", "+5", "20 39", "0x7g7", "2_039", "12ab"] {
+            let err = ZKPSystem::builder()
+                .with_prime_str(malformed)
+                .with_order_str("1019")
+                .with_generator_str("4")
+                .with_second_generator_str("9")
+                .build()
+                .err()
+                .unwrap();
+            assert_eq!(err, ZKPError::InvalidNumber("p"), "{:?}", malformed);
+        }
+    }
+
+    #[test]
+    fn test_verify_detailed() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p.clone(), q, alpha, beta);
+        let prover = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+        let verifier = Verifier::new(&system);
+        let (y1, y2) = prover.public_values();
+        let ((r1, r2), k) = prover.generate_commitments();
+        let c = verifier.generate_challenge();
+        let s = prover.generate_response(&c, &k);
+
+        let outcome = verifier.verify_detailed((&r1, &r2), &c, &s, (y1, y2));
+        assert!(outcome.accepted && outcome.first_check && outcome.second_check);
+
+        let tampered_r2 = &r2 * &system.parameters().beta % &p;
+        let outcome = verifier.verify_detailed((&r1, &tampered_r2), &c, &s, (y1, y2));
+        assert!(!outcome.accepted);
+        assert!(outcome.first_check);
+        assert!(!outcome.second_check);
+        assert_eq!(
+            outcome.accepted,
+            verifier.verify((&r1, &tampered_r2), &c, &s, (y1, y2))
+        );
+    }
+
+    #[test]
+    fn test_verifier_rejects_proof_from_another_group() {
+        let (alpha, beta, p, q) = ZKPUtils::get_2048_bit_constants();
+        let client_system = ZKPSystem::new(p, q, alpha, beta);
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let server_system = ZKPSystem::new(p, q, alpha, beta);
+
+        let prover = Prover::new(
+            &client_system,
+            ZKPUtils::generate_random_below(client_system.get_order()),
+        );
+        let client_verifier = Verifier::new(&client_system);
+        let server_verifier = Verifier::new(&server_system);
+        let (y1, y2) = prover.public_values();
+        let ((r1, r2), k) = prover.generate_commitments();
+        let c = server_verifier.generate_challenge();
+        let s = prover.generate_response(&c, &k);
+
+        assert!(client_verifier.verify((&r1, &r2), &c, &s, (y1, y2)));
+        for value in [&r1, &r2, y1, y2] {
+            assert!(!server_system.is_group_element(value));
+        }
+        assert!(!server_verifier.verify((&r1, &r2), &c, &s, (y1, y2)));
+        let outcome = server_verifier.verify_detailed((&r1, &r2), &c, &s, (y1, y2));
+        assert!(!outcome.accepted);
+    }
+
+    #[test]
+    fn test_export_users() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = Arc::new(ZKPSystem::new(p, q, alpha, beta));
+        let service = AuthService::new(system.clone());
+        let mut expected = Vec::new();
+        for user in ["carol", "alice", "bob"] {
+            let secret = ZKPUtils::generate_random_below(system.get_order());
+            let (y1, y2) = system.compute_public_values(&secret);
+            let record = RegistrationRecord::new(y1, y2).with_label(format!("{}-laptop", user));
+            service.register_record(user, record.clone()).unwrap();
+            expected.push((user.to_string(), record));
+        }
+        expected.sort_by(|a, b| a.0.cmp(&b.0));
+
+        assert_eq!(service.export_users(), expected);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_registration_record_serde() {
+        let record = RegistrationRecord {
+            y1: BigUint::from(0xabcdu32),
+            y2: BigUint::from(7u32),
+            registered_at: std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            label: Some("phone".to_string()),
+        };
+        let json = serde_json::to_string(&record).unwrap();
+        assert!(json.contains("\"y1\":\"abcd\""));
+        assert!(json.contains("\"registered_at\":1700000000"));
+        let parsed: RegistrationRecord = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, record);
+
+        let malformed = json.replace("abcd", "xyz");
+        assert!(serde_json::from_str::<RegistrationRecord>(&malformed).is_err());
+    }
+
+    /// Hands out 1, 2, 3, ... so transcripts are reproducible.
+    struct CountingChallenges(std::sync::atomic::AtomicU32);
+
+    impl ChallengeSource for CountingChallenges {
+        fn next_challenge(&self, q: &BigUint) -> BigUint {
+            let n = self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+            BigUint::from(n) % q
+        }
+    }
+
+    #[test]
+    fn test_verifier_with_challenge_source() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+        let secret = BigUint::from(123456789u32);
+        let nonce = BigUint::from(987654321u32);
+        let (y1, y2) = system.compute_public_values(&secret);
+
+        let run = || {
+            let verifier = Verifier::new(&system)
+                .with_challenge_source(CountingChallenges(Default::default()));
+            (0..3)
+                .map(|_| {
+                    let (r1, r2) = system.compute_commitments(&nonce);
+                    let c = verifier.generate_challenge();
+                    let s = system.compute_response(&nonce, &c, &secret);
+                    assert!(verifier.verify((&r1, &r2), &c, &s, (&y1, &y2)));
+                    (c, s)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let transcripts = run();
+        let challenges: Vec<_> = transcripts.iter().map(|(c, _)| c.clone()).collect();
+        assert_eq!(challenges, [1u32, 2, 3].map(BigUint::from));
+        assert_eq!(run(), transcripts);
+    }
+
+    /// Chaum-Pedersen over the additive group `Z_q` with generators 3 and 5:
+    /// insecure, but a different backend for the conformance checks.
+    struct AdditiveMock {
+        q: BigUint,
+    }
+
+    impl AdditiveMock {
+        fn new() -> Self {
+            Self {
+                q: (BigUint::from(1u32) << 127) - 1u32,
+            }
+        }
+
+        fn mul(&self, a: u32, b: &BigUint) -> BigUint {
+            BigUint::from(a) * b % &self.q
+        }
+    }
+
+    impl ZKPProtocol for AdditiveMock {
+        fn compute_commitments(&self, randomness: &BigUint) -> (BigUint, BigUint) {
+            (self.mul(3, randomness), self.mul(5, randomness))
+        }
+
+        fn compute_response(
+            &self,
+            randomness: &BigUint,
+            challenge: &BigUint,
+            secret: &BigUint,
+        ) -> BigUint {
+            (randomness + &self.q - challenge * secret % &self.q) % &self.q
+        }
+
+        fn verify(
+            &self,
+            commitments: (&BigUint, &BigUint),
+            challenge: &BigUint,
+            response: &BigUint,
+            public_values: (&BigUint, &BigUint),
+        ) -> bool {
+            let (r1, r2) = commitments;
+            let (y1, y2) = public_values;
+            *r1 == (self.mul(3, response) + challenge * y1) % &self.q
+                && *r2 == (self.mul(5, response) + challenge * y2) % &self.q
+        }
+
+        fn compute_public_values(&self, secret: &BigUint) -> (BigUint, BigUint) {
+            self.compute_commitments(secret)
+        }
+
+        fn get_order(&self) -> &BigUint {
+            &self.q
+        }
+
+        fn is_group_element(&self, value: &BigUint) -> bool {
+            *value < self.q
+        }
+    }
+
+    #[test]
+    fn test_zkp_protocol_conformance() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p.clone(), q.clone(), alpha.clone(), beta.clone());
+        assert_zkp_protocol_conformance!(system);
+        let precomputed = ZKPSystem::builder()
+            .with_prime(p)
+            .with_order(q)
+            .with_generator(alpha)
+            .with_second_generator(beta)
+            .with_precomputation()
+            .build()
+            .unwrap();
+        assert_zkp_protocol_conformance!(precomputed);
+
+        let mock = AdditiveMock::new();
+        let outside = mock.q.clone();
+        assert_zkp_protocol_conformance!(mock, outside);
+    }
+
+    #[test]
+    #[should_panic(expected = "must not be a group element")]
+    fn test_conformance_catches_missing_membership_check() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+        // `DefaultBatch` keeps the default `is_group_element`, which accepts
+        // anything.
+        assert_zkp_protocol_conformance!(DefaultBatch(&system));
+    }
+
+    #[test]
+    fn test_compute_response_matches_signed_reference() {
+        use num_bigint::BigInt;
+
+        let params = ZKPParameters::from(ZKPUtils::get_1024_bit_constants());
+        let q = params.q.clone();
+        let reference = |k: &BigUint, c: &BigUint, x: &BigUint| {
+            let q = BigInt::from(q.clone());
+            let diff = BigInt::from(k.clone()) - BigInt::from(c.clone()) * BigInt::from(x.clone());
+            ((diff % &q + &q) % &q).to_biguint().unwrap()
+        };
+
+        let x = ZKPUtils::generate_random_below(&q);
+        let c = ZKPUtils::generate_random_below(&q);
+        let cx = &c * &x % &q;
+        let mut cases = vec![
+            (BigUint::from(0u32), c.clone(), x.clone()),
+            (cx.clone(), c.clone(), x.clone()),
+            (&cx + 1u32, c.clone(), x.clone()),
+            (BigUint::from(5u32), BigUint::from(0u32), x.clone()),
+        ];
+        for _ in 0..32 {
+            cases.push((
+                ZKPUtils::generate_random_below(&q),
+                ZKPUtils::generate_random_below(&q),
+                ZKPUtils::generate_random_below(&q),
+            ));
+        }
+        for (k, c, x) in &cases {
+            let s = params.compute_response(k, c, x);
+            assert!(s < q);
+            assert_eq!(s, reference(k, c, x));
+        }
+    }
+
+    #[test]
+    fn test_clone_shares_precomputed_tables() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let builds = precompute::FixedBaseTable::builds_on_this_thread();
+        let system = ZKPSystem::builder()
+            .with_prime(p)
+            .with_order(q)
+            .with_generator(alpha)
+            .with_second_generator(beta)
+            .with_precomputation()
+            .build()
+            .unwrap();
+        let built = precompute::FixedBaseTable::builds_on_this_thread();
+        assert_eq!(built - builds, 2);
+
+        let clone = system.clone();
+        assert_eq!(precompute::FixedBaseTable::builds_on_this_thread(), built);
+        assert_eq!(clone.precompute_window(), system.precompute_window());
+        assert!(clone.precompute_window().is_some());
+
+        let prover = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+        let (y1, y2) = prover.public_values();
+        for verifier in [Verifier::new(&system), Verifier::new(&clone)] {
+            let ((r1, r2), k) = prover.generate_commitments();
+            let c = verifier.generate_challenge();
+            let s = prover.generate_response(&c, &k);
+            assert!(verifier.verify((&r1, &r2), &c, &s, (y1, y2)));
+        }
+        assert_eq!(precompute::FixedBaseTable::builds_on_this_thread(), built);
+    }
+
+    #[test]
+    fn test_verify_proof_from_prover_flow() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q, alpha, beta);
+        let prover = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+        let verifier = Verifier::new(&system);
+
+        let (commitments, k) = prover.generate_commitments();
+        let challenge = verifier.generate_challenge();
+        let response = prover.generate_response(&challenge, &k);
+        let mut transcript = Transcript {
+            public_values: prover.public_values_owned(),
+            commitments,
+            challenge,
+            response,
+        };
+        assert!(verifier.verify_proof(&transcript));
+
+        transcript.response += 1u32;
+        assert!(!verifier.verify_proof(&transcript));
+    }
+
+    #[test]
+    fn test_short_challenge_modulus() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let c_max: BigUint = BigUint::from(1u32) << 64;
+        let system = ZKPSystem::builder()
+            .with_prime(p.clone())
+            .with_order(q.clone())
+            .with_generator(alpha.clone())
+            .with_second_generator(beta.clone())
+            .with_challenge_modulus(c_max.clone())
+            .build()
+            .unwrap();
+        assert_eq!(system.challenge_bound(), &c_max);
+        let prover = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+        let verifier = Verifier::new(&system);
+        assert_eq!(verifier.challenge_entropy_bits(), 64);
+        let (y1, y2) = prover.public_values();
+
+        for _ in 0..16 {
+            let ((r1, r2), k) = prover.generate_commitments();
+            let c = verifier.generate_challenge();
+            assert!(c < c_max);
+            let s = prover.generate_response(&c, &k);
+            assert!(verifier.verify((&r1, &r2), &c, &s, (y1, y2)));
+        }
+
+        // The equations hold for a challenge of c_max, but it is out of range.
+        let ((r1, r2), k) = prover.generate_commitments();
+        let s = prover.generate_response(&c_max, &k);
+        assert!(!verifier.verify((&r1, &r2), &c_max, &s, (y1, y2)));
+        let unbounded = ZKPSystem::new(p.clone(), q.clone(), alpha.clone(), beta.clone());
+        assert!(Verifier::new(&unbounded).verify((&r1, &r2), &c_max, &s, (y1, y2)));
+
+        let proof = prover.prove_non_interactive();
+        assert!(verifier.non_interactive_challenge_with::<Sha256>(&proof, (y1, y2)) < c_max);
+        assert!(verifier.verify_non_interactive(&proof, (y1, y2)));
+
+        for bad in [BigUint::from(0u32), &q + 1u32] {
+            let err = ZKPSystem::builder()
+                .with_prime(p.clone())
+                .with_order(q.clone())
+                .with_generator(alpha.clone())
+                .with_second_generator(beta.clone())
+                .with_challenge_modulus(bad)
+                .build()
+                .err()
+                .unwrap();
+            assert_eq!(err, ZKPError::InvalidChallengeModulus);
+        }
+    }
+
+    #[test]
+    fn test_auth_service_one_challenge_per_user() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = Arc::new(ZKPSystem::new(p, q, alpha, beta));
+        let prover = Prover::new(
+            &*system,
+            ZKPUtils::generate_random_below(system.get_order()),
+        );
+        let (y1, y2) = prover.public_values_owned();
+        let service = |policy| {
+            let service = AuthService::new(system.clone())
+                .with_per_user_policy(policy)
+                .with_session_ttl(Duration::from_secs(60));
+            service.register("alice", y1.clone(), y2.clone()).unwrap();
+            service.register("bob", y1.clone(), y2.clone()).unwrap();
+            service
+        };
+
+        let replacing = service(PerUserPolicy::ReplacePrevious);
+        let ((r1, r2), k) = prover.generate_commitments();
+        let (first, c1) = replacing
+            .create_challenge("alice", r1.clone(), r2.clone())
+            .unwrap();
+        let (bobs, _) = replacing
+            .create_challenge("bob", r1.clone(), r2.clone())
+            .unwrap();
+        let (second, c2) = replacing
+            .create_challenge("alice", r1.clone(), r2.clone())
+            .unwrap();
+        assert_eq!(replacing.active_sessions(), 2);
+        assert_eq!(
+            replacing
+                .verify(&first, &prover.generate_response(&c1, &k))
+                .unwrap_err(),
+            VerifierError::SessionNotFound
+        );
+        assert!(replacing
+            .verify(&second, &prover.generate_response(&c2, &k))
+            .is_ok());
+        assert_eq!(replacing.active_sessions(), 1);
+        assert!(replacing.verify(&bobs, &BigUint::from(0u32)).is_err());
+
+        let refusing = service(PerUserPolicy::RefuseNew);
+        let (first, c1) = refusing
+            .create_challenge("alice", r1.clone(), r2.clone())
+            .unwrap();
+        assert_eq!(
+            refusing
+                .create_challenge("alice", r1.clone(), r2.clone())
+                .unwrap_err(),
+            ZKPError::ChallengeAlreadyPending
+        );
+        assert!(refusing
+            .verify(&first, &prover.generate_response(&c1, &k))
+            .is_ok());
+        assert!(refusing.create_challenge("alice", r1, r2).is_ok());
+    }
+
+    #[test]
+    fn test_session_manager_capacity_policies() {
+        let rejecting = SessionManager::new().with_max_sessions(3, EvictionPolicy::RejectNew);
+        let mut generate = counter_generator();
+        let ids: Vec<_> = (0..3)
+            .map(|_| {
+                rejecting
+                    .create("alice", commitments(), public_values(), &mut generate)
+                    .unwrap()
+                    .0
+            })
+            .collect();
+        assert_eq!(
+            rejecting.create("alice", commitments(), public_values(), &mut generate),
+            Err(ZKPError::SessionCapacityExceeded)
+        );
+        assert_eq!(rejecting.len(), 3);
+        // Answering a challenge frees its slot.
+        assert!(rejecting.consume(&ids[1]).is_some());
+        assert!(rejecting
+            .create("alice", commitments(), public_values(), &mut generate)
+            .is_ok());
+        assert_eq!(rejecting.len(), 3);
+
+        let evicting = SessionManager::new().with_max_sessions(3, EvictionPolicy::EvictOldest);
+        let ids: Vec<_> = (0..5)
+            .map(|_| {
+                evicting
+                    .create("bob", commitments(), public_values(), &mut generate)
+                    .unwrap()
+                    .0
+            })
+            .collect();
+        assert_eq!(evicting.len(), 3);
+        assert_eq!(evicting.consume(&ids[0]), None);
+        assert_eq!(evicting.consume(&ids[1]), None);
+        for auth_id in &ids[2..] {
+            assert!(evicting.consume(auth_id).is_some());
+        }
+
+        // Expired challenges make room before the policy applies.
+        let expiring = SessionManager::with_ttl(Duration::ZERO)
+            .with_max_sessions(1, EvictionPolicy::RejectNew);
+        expiring
+            .create("carol", commitments(), public_values(), &mut generate)
+            .unwrap();
+        assert!(expiring
+            .create("carol", commitments(), public_values(), &mut generate)
+            .is_ok());
+        assert_eq!(expiring.len(), 1);
+    }
+
+    #[test]
+    fn test_bytes_to_biguint_checked() {
+        let max = BigUint::from(0x1234u32);
+        assert_eq!(
+            ZKPUtils::bytes_to_biguint_checked(&[0x12, 0x33], &max),
+            Ok(BigUint::from(0x1233u32))
+        );
+        assert_eq!(
+            ZKPUtils::bytes_to_biguint_checked(&[0], &max),
+            Ok(BigUint::from(0u32))
+        );
+        assert_eq!(
+            ZKPUtils::bytes_to_biguint_checked(&[], &max),
+            Err(ZKPError::NonCanonicalEncoding)
+        );
+        assert_eq!(
+            ZKPUtils::bytes_to_biguint_checked(&[0, 0x12], &max),
+            Err(ZKPError::NonCanonicalEncoding)
+        );
+        assert_eq!(
+            ZKPUtils::bytes_to_biguint_checked(&[1, 0, 0], &max),
+            Err(ZKPError::NonCanonicalEncoding)
+        );
+        assert_eq!(
+            ZKPUtils::bytes_to_biguint_checked(&[0x12, 0x34], &max),
+            Err(ZKPError::ValueOutOfRange)
+        );
+        assert_eq!(
+            ZKPUtils::bytes_to_biguint_checked(&[0xff, 0xff], &max),
+            Err(ZKPError::ValueOutOfRange)
+        );
+    }
+
+    #[test]
+    fn test_prove_and_verify() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p, q.clone(), alpha, beta);
+        let secret = ZKPUtils::generate_random_below(&q);
+        assert!(system.prove_and_verify(&secret));
+
+        let (y1, y2) = system.compute_public_values(&(&secret + 1u32));
+        assert!(!system.prove_and_verify_against(&secret, (&y1, &y2)));
+    }
+
+    #[test]
+    fn test_verify_registration_proof() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = ZKPSystem::new(p.clone(), q.clone(), alpha, beta);
+        let secret = ZKPUtils::generate_random_below(&q);
+        let k = ZKPUtils::generate_random_below(&q);
+        let (y1, y2) = system.compute_public_values(&secret);
+        let (r1, r2) = system.compute_commitments(&k);
+        let c = AuthService::possession_challenge(&q, "alice", (&y1, &y2), (&r1, &r2));
+        let s = system.compute_response(&k, &c, &secret);
+        assert!(system.verify_registration_proof(&y1, &y2, (&r1, &r2), &c, &s));
+
+        let tampered = (&s + 1u32) % &q;
+        assert!(!system.verify_registration_proof(&y1, &y2, (&r1, &r2), &c, &tampered));
+
+        // -y1 has order 2q, so with an even challenge the equations alone
+        // still hold; only the subgroup check rejects it.
+        let c = (&c >> 1u32) << 1u32;
+        let s = system.compute_response(&k, &c, &secret);
+        let negated = &p - &y1;
+        assert!(system.verify((&r1, &r2), &c, &s, (&negated, &y2)));
+        assert!(!system.verify_registration_proof(&negated, &y2, (&r1, &r2), &c, &s));
+        assert!(system.verify_registration_proof(&y1, &y2, (&r1, &r2), &c, &s));
+    }
 }
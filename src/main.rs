@@ -1,34 +1,314 @@
 use crate::auth_capnp::auth;
-use crate::system::ZKPSystem;
+use crate::system::{ResponseConvention, ZKPSystem};
 use crate::utils::ZKPUtils;
-use crate::actors::{Prover, Verifier};
+use crate::actors::Prover;
+use crate::error::ZKPError;
+use crate::service::AuthService;
+use crate::session::{EvictionPolicy, PerUserPolicy};
+use crate::transport::{run_prover, ProverTransport};
 use capnp::capability::Promise;
 use capnp_rpc::{rpc_twoparty_capnp, twoparty, RpcSystem, pry};
 use num_bigint::BigUint;
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::cell::RefCell;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
 use crate::protocol::ZKPProtocol;
 
 pub mod auth_capnp;
 pub mod actors;
+pub mod audit;
+pub mod challenge;
+pub mod error;
+pub mod group;
+pub mod metrics;
+pub mod precompute;
 pub mod protocol;
+pub mod registry;
+pub mod replay;
+pub mod secret;
+pub mod service;
+pub mod session;
 pub mod system;
+pub mod transcript;
+pub mod transport;
 pub mod utils;
 
+/// Errors raised while decoding client-supplied request fields.
+#[derive(Debug)]
+enum RequestError {
+    FieldTooLarge {
+        field: &'static str,
+        len: usize,
+        max: usize,
+    },
+    NonCanonicalEncoding {
+        field: &'static str,
+        width: usize,
+    },
+    EmptyField {
+        field: &'static str,
+    },
+    OutOfRange {
+        field: &'static str,
+    },
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::FieldTooLarge { field, len, max } => write!(
+                f,
+                "FieldTooLarge: `{}` is {} bytes, maximum is {}",
+                field, len, max
+            ),
+            RequestError::NonCanonicalEncoding { field, width } => write!(
+                f,
+                "NonCanonicalEncoding: `{}` must be minimal big-endian of at most {} bytes",
+                field, width
+            ),
+            RequestError::EmptyField { field } => write!(f, "EmptyField: `{}` must not be empty", field),
+            RequestError::OutOfRange { field } => write!(f, "OutOfRange: `{}` is not below its allowed maximum", field),
+        }
+    }
+}
+
+impl From<RequestError> for capnp::Error {
+    fn from(e: RequestError) -> Self {
+        capnp::Error::failed(e.to_string())
+    }
+}
+
+/// Decodes a big-endian number sent by the peer, rejecting empty fields and
+/// anything wider than `exclusive_max` before it reaches `modpow`.
+///
+/// Group elements are bounded by `p` and scalars such as `c` and `s` by `q`.
+/// Only the canonical encoding of a value below `exclusive_max` is accepted
+/// (see `ZKPUtils::bytes_to_biguint_checked`), so the same value cannot be
+/// sent as several different byte strings.
+fn read_field(bytes: &[u8], field: &'static str, exclusive_max: &BigUint) -> Result<BigUint, RequestError> {
+    if bytes.is_empty() {
+        return Err(RequestError::EmptyField { field });
+    }
+    let width = exclusive_max.bits().div_ceil(8) as usize;
+    if bytes.len() > width {
+        return Err(RequestError::FieldTooLarge {
+            field,
+            len: bytes.len(),
+            max: width,
+        });
+    }
+    ZKPUtils::bytes_to_biguint_checked(bytes, exclusive_max).map_err(|e| match e {
+        ZKPError::ValueOutOfRange => RequestError::OutOfRange { field },
+        _ => RequestError::NonCanonicalEncoding { field, width },
+    })
+}
+
+impl From<ZKPError> for capnp::Error {
+    fn from(e: ZKPError) -> Self {
+        capnp::Error::failed(e.to_string())
+    }
+}
+
+/// Errors returned by the client-side `register` and `login` sequences.
+#[derive(Debug)]
+enum ClientError {
+    /// The call failed in transport or the server refused the request.
+    Rpc(capnp::Error),
+    /// The server checked the proof and rejected it.
+    AuthenticationFailed { user: String },
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Rpc(e) => write!(f, "{}", e),
+            ClientError::AuthenticationFailed { user } => write!(f, "Authentication failed for user '{}'", user),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<capnp::Error> for ClientError {
+    fn from(e: capnp::Error) -> Self {
+        ClientError::Rpc(e)
+    }
+}
+
+/// Snapshot of the server's in-memory state, for monitoring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ServerStats {
+    registered_users: usize,
+    active_sessions: usize,
+}
+
+/// Runs CPU-heavy work, subgroup checks and verification, off the RPC event
+/// loop.
+///
+/// At most `workers` jobs run at once on tokio's blocking threads; further
+/// jobs wait for a permit, so a burst of logins cannot starve the accept loop.
+#[derive(Clone)]
+struct VerifyPool {
+    permits: Arc<tokio::sync::Semaphore>,
+}
+
+impl VerifyPool {
+    fn new(workers: usize) -> Self {
+        Self {
+            permits: Arc::new(tokio::sync::Semaphore::new(workers.max(1))),
+        }
+    }
+
+    /// Defaults to one worker per available CPU.
+    fn default_workers() -> usize {
+        std::thread::available_parallelism().map_or(1, |n| n.get())
+    }
+
+    async fn run<F, R>(&self, job: F) -> R
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let _permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("verify pool closed");
+        tokio::task::spawn_blocking(job)
+            .await
+            .expect("verification job panicked")
+    }
+
+    /// Like `run`, but stops waiting after `budget` and returns
+    /// `ZKPError::VerifyTimeout`.
+    ///
+    /// A blocking thread cannot be interrupted, so an abandoned job still runs
+    /// to completion. It keeps its permit until then, so timed-out jobs keep
+    /// counting against the worker limit.
+    async fn run_with_timeout<F, R>(&self, budget: Duration, job: F) -> Result<R, ZKPError>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("verify pool closed");
+        let handle = tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            job()
+        });
+        match tokio::time::timeout(budget, handle).await {
+            Ok(result) => Ok(result.expect("verification job panicked")),
+            Err(_) => Err(ZKPError::VerifyTimeout),
+        }
+    }
+}
+
+/// Cap'n Proto adapter over `AuthService`: decodes requests, forwards them,
+/// and encodes the results.
+#[derive(Clone)]
 struct AuthImpl {
-    system: Arc<ZKPSystem>,
-    // user -> (y1, y2)
-    users: Arc<Mutex<HashMap<String, (BigUint, BigUint)>>>,
-    // auth_id -> (user, challenge, r1, r2)
-    sessions: Arc<Mutex<HashMap<String, (String, BigUint, BigUint, BigUint)>>>,
+    service: AuthService,
+    pool: VerifyPool,
+    verify_timeout: Option<Duration>,
+    admin_token: Option<String>,
 }
 
 impl AuthImpl {
     fn new(system: Arc<ZKPSystem>) -> Self {
+        Self::with_workers(system, VerifyPool::default_workers())
+    }
+
+    fn with_workers(system: Arc<ZKPSystem>, workers: usize) -> Self {
         Self {
-            system,
-            users: Arc::new(Mutex::new(HashMap::new())),
-            sessions: Arc::new(Mutex::new(HashMap::new())),
+            service: AuthService::new(system),
+            pool: VerifyPool::new(workers),
+            verify_timeout: None,
+            admin_token: None,
+        }
+    }
+
+    /// Reports service events to `sink`.
+    #[cfg(feature = "prometheus")]
+    fn with_metrics(mut self, sink: Arc<dyn metrics::MetricsSink>) -> Self {
+        self.service = self.service.with_metrics(sink);
+        self
+    }
+
+    /// Expires challenges that are not answered within `ttl`.
+    fn with_session_ttl(mut self, ttl: Duration) -> Self {
+        self.service = self.service.with_session_ttl(ttl);
+        self
+    }
+
+    /// Holds at most `max` pending challenges; see `AuthService::with_max_sessions`.
+    fn with_max_sessions(mut self, max: usize, policy: EvictionPolicy) -> Self {
+        self.service = self.service.with_max_sessions(max, policy);
+        self
+    }
+
+    /// Limits pending challenges per user; see `AuthService::with_per_user_policy`.
+    fn with_per_user_policy(mut self, policy: PerUserPolicy) -> Self {
+        self.service = self.service.with_per_user_policy(policy);
+        self
+    }
+
+    /// Abandons verifications that take longer than `budget`; `None` waits
+    /// for as long as it takes.
+    fn with_verify_timeout(mut self, budget: Option<Duration>) -> Self {
+        self.verify_timeout = budget;
+        self
+    }
+
+    /// Checks the response `s` to `auth_id` on the pool, within the configured
+    /// time budget if any.
+    async fn verify_with_timeout(self, auth_id: String, s: BigUint) -> Result<String, ZKPError> {
+        let service = self.service;
+        let job = move || service.verify(&auth_id, &s);
+        let outcome = match self.verify_timeout {
+            Some(budget) => self.pool.run_with_timeout(budget, job).await?,
+            None => self.pool.run(job).await,
+        };
+        outcome.map_err(ZKPError::from)
+    }
+
+    /// Enables admin calls such as `exportUsers` for callers presenting
+    /// `token`; `None` disables them.
+    fn with_admin_token(mut self, token: Option<String>) -> Self {
+        self.admin_token = token;
+        self
+    }
+
+    /// Checks the token sent with an admin call. Digests are compared so the
+    /// time taken does not depend on how much of the token matched.
+    fn check_admin_token(&self, token: &str) -> Result<(), capnp::Error> {
+        use sha2::{Digest, Sha256};
+
+        let Some(expected) = &self.admin_token else {
+            return Err(capnp::Error::failed("AdminDisabled: admin calls are not enabled".to_string()));
+        };
+        if Sha256::digest(token.as_bytes()) != Sha256::digest(expected.as_bytes()) {
+            return Err(capnp::Error::failed("Unauthorized: invalid admin token".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Refuses plain `register` calls when `required` is set.
+    fn with_proof_required(mut self, required: bool) -> Self {
+        self.service = self.service.with_proof_required(required);
+        self
+    }
+
+    fn stats(&self) -> ServerStats {
+        ServerStats {
+            registered_users: self.service.registered_users(),
+            active_sessions: self.service.active_sessions(),
         }
     }
 }
@@ -46,13 +326,49 @@ impl auth::Server for AuthImpl {
             Ok(u) => u,
             Err(_) => return Promise::err(capnp::Error::failed("Invalid user string".to_string())),
         };
-        let y1 = BigUint::from_bytes_be(pry!(request_reader.get_y1()));
-        let y2 = BigUint::from_bytes_be(pry!(request_reader.get_y2()));
+        let group = self.service.system().parameters();
+        let y1 = pry!(read_field(pry!(request_reader.get_y1()), "y1", &group.p));
+        let y2 = pry!(read_field(pry!(request_reader.get_y2()), "y2", &group.p));
 
         println!("Registering user: {}", user);
-        self.users.lock().unwrap().insert(user, (y1, y2));
 
-        Promise::ok(())
+        let service = self.service.clone();
+        let pool = self.pool.clone();
+        Promise::from_future(async move {
+            pool.run(move || service.register(&user, y1, y2))
+                .await
+                .map_err(capnp::Error::from)
+        })
+    }
+
+    fn register_with_proof(
+        &mut self,
+        params: auth::RegisterWithProofParams,
+        mut _results: auth::RegisterWithProofResults,
+    ) -> Promise<(), ::capnp::Error> {
+        let request = pry!(params.get());
+        let request_reader = pry!(request.get_request());
+        let user = pry!(request_reader.get_user()).to_string();
+        let user = match user {
+            Ok(u) => u,
+            Err(_) => return Promise::err(capnp::Error::failed("Invalid user string".to_string())),
+        };
+        let group = self.service.system().parameters();
+        let y1 = pry!(read_field(pry!(request_reader.get_y1()), "y1", &group.p));
+        let y2 = pry!(read_field(pry!(request_reader.get_y2()), "y2", &group.p));
+        let r1 = pry!(read_field(pry!(request_reader.get_r1()), "r1", &group.p));
+        let r2 = pry!(read_field(pry!(request_reader.get_r2()), "r2", &group.p));
+        let s = pry!(read_field(pry!(request_reader.get_s()), "s", &group.q));
+
+        println!("Registering user with proof of possession: {}", user);
+
+        let service = self.service.clone();
+        let pool = self.pool.clone();
+        Promise::from_future(async move {
+            pool.run(move || service.register_with_proof(&user, y1, y2, (r1, r2), &s))
+                .await
+                .map_err(capnp::Error::from)
+        })
     }
 
     fn create_authentication_challenge(
@@ -67,33 +383,27 @@ impl auth::Server for AuthImpl {
             Ok(u) => u,
             Err(_) => return Promise::err(capnp::Error::failed("Invalid user string".to_string())),
         };
-        let r1 = BigUint::from_bytes_be(pry!(request_reader.get_r1()));
-        let r2 = BigUint::from_bytes_be(pry!(request_reader.get_r2()));
+        let group = self.service.system().parameters();
+        let r1 = pry!(read_field(pry!(request_reader.get_r1()), "r1", &group.p));
+        let r2 = pry!(read_field(pry!(request_reader.get_r2()), "r2", &group.p));
 
         println!("Creating challenge for user: {}", user);
 
-        // Verify user exists
-        if !self.users.lock().unwrap().contains_key(&user) {
-            return Promise::err(capnp::Error::failed("User not found".to_string()));
-        }
-
-        let verifier = Verifier::new(&*self.system);
-        let challenge = verifier.generate_challenge();
-        let auth_id = ZKPUtils::generate_random_string(16);
-
-        {
-            let mut sessions = self.sessions.lock().unwrap();
-            sessions.insert(
-                auth_id.clone(),
-                (user, challenge.clone(), r1, r2),
-            );
-        }
+        let convention = group.convention;
+        let service = self.service.clone();
+        let pool = self.pool.clone();
+        Promise::from_future(async move {
+            let (auth_id, challenge) = pool.run(move || service.create_challenge(&user, r1, r2)).await?;
 
-        let mut response = results.get().init_response();
-        response.set_auth_id(&auth_id);
-        response.set_c(&challenge.to_bytes_be());
-
-        Promise::ok(())
+            let mut response = results.get().init_response();
+            response.set_auth_id(&auth_id);
+            response.set_c(&challenge.to_bytes_be());
+            response.set_convention(match convention {
+                ResponseConvention::Subtractive => auth_capnp::ResponseConvention::Subtractive,
+                ResponseConvention::Additive => auth_capnp::ResponseConvention::Additive,
+            });
+            Ok(())
+        })
     }
 
     fn verify_authentication(
@@ -108,74 +418,586 @@ impl auth::Server for AuthImpl {
             Ok(id) => id,
             Err(_) => return Promise::err(capnp::Error::failed("Invalid auth_id string".to_string())),
         };
-        let s = BigUint::from_bytes_be(pry!(request_reader.get_s()));
+        let group = self.service.system().parameters();
+        let s = pry!(read_field(pry!(request_reader.get_s()), "s", &group.q));
 
         println!("Verifying authentication for auth_id: {}", auth_id);
 
-        let session = {
-            let mut sessions = self.sessions.lock().unwrap();
-            sessions.remove(&auth_id)
-        };
+        let auth_impl = self.clone();
+        Promise::from_future(async move {
+            match auth_impl.verify_with_timeout(auth_id.clone(), s).await {
+                Ok(session_id) => {
+                    println!("Authentication successful for auth_id: {}", auth_id);
+                    let mut response = results.get().init_response();
+                    response.set_accepted(true);
+                    response.set_session_id(&session_id);
+                    Ok(())
+                }
+                // A checked and rejected proof is an answer, not a failed call.
+                Err(ZKPError::AuthenticationFailed) => {
+                    println!("Authentication failed for auth_id: {}", auth_id);
+                    results.get().init_response().set_accepted(false);
+                    Ok(())
+                }
+                Err(e) => {
+                    println!("Authentication failed for auth_id: {}", auth_id);
+                    Err(e.into())
+                }
+            }
+        })
+    }
 
-        let (user, challenge, r1, r2) = match session {
-            Some(s) => s,
-            None => return Promise::err(capnp::Error::failed("Session not found".to_string())),
-        };
+    /// Reports liveness and the group size; touches no user or session state.
+    fn health(
+        &mut self,
+        _params: auth::HealthParams,
+        mut results: auth::HealthResults,
+    ) -> Promise<(), ::capnp::Error> {
+        let group_bits = self.service.system().parameters().p.bits();
+        let mut response = results.get().init_response();
+        response.set_status("ok");
+        response.set_group_bits(group_bits as u32);
+        Promise::ok(())
+    }
 
-        let users = self.users.lock().unwrap();
-        let (y1, y2) = match users.get(&user) {
-            Some(u) => u,
-            None => return Promise::err(capnp::Error::failed("User not found".to_string())),
+    fn export_users(
+        &mut self,
+        params: auth::ExportUsersParams,
+        mut results: auth::ExportUsersResults,
+    ) -> Promise<(), ::capnp::Error> {
+        let request = pry!(params.get());
+        let request_reader = pry!(request.get_request());
+        let token = match pry!(request_reader.get_admin_token()).to_str() {
+            Ok(t) => t,
+            Err(_) => return Promise::err(capnp::Error::failed("Invalid admin token string".to_string())),
         };
+        pry!(self.check_admin_token(token));
 
-        let verifier = Verifier::new(&*self.system);
-        let is_valid = verifier.verify(
-            (&r1, &r2),
-            &challenge,
-            &s,
-            (y1, y2),
-        );
+        let users = self.service.export_users();
+        println!("Exporting {} users", users.len());
+        let mut list = results.get().init_response().init_users(users.len() as u32);
+        for (i, (user, record)) in users.iter().enumerate() {
+            let registered_at = record
+                .registered_at
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs());
+            let mut entry = list.reborrow().get(i as u32);
+            entry.set_user(user);
+            entry.set_y1(&record.y1.to_bytes_be());
+            entry.set_y2(&record.y2.to_bytes_be());
+            entry.set_registered_at(registered_at);
+            if let Some(label) = &record.label {
+                entry.set_label(label);
+            }
+        }
+        Promise::ok(())
+    }
+}
 
-        if is_valid {
-            println!("Authentication successful for user: {}", user);
-            let session_id = ZKPUtils::generate_random_string(32);
-            results.get().init_response().set_session_id(&session_id);
-            Promise::ok(())
+/// Prints human-readable client output unless `--json` was requested.
+macro_rules! say {
+    ($json:expr, $($arg:tt)*) => {
+        if !$json {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Renders a flat JSON object with string values, for `--json` output.
+fn json_object(fields: &[(&str, &str)]) -> String {
+    let escape = |s: &str| {
+        let mut out = String::with_capacity(s.len());
+        for ch in s.chars() {
+            match ch {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+                c => out.push(c),
+            }
+        }
+        out
+    };
+    let body: Vec<String> = fields
+        .iter()
+        .map(|(k, v)| format!("\"{}\":\"{}\"", escape(k), escape(v)))
+        .collect();
+    format!("{{{}}}", body.join(","))
+}
+
+/// Carries the prover's side of the protocol over the `Auth` capability.
+///
+/// The challenge RPC both delivers the commitments and returns `c`, and the
+/// verification RPC both delivers `s` and returns the decision, so each
+/// `send_*` call performs the round trip and the matching `recv_*` call hands
+/// back what it received.
+///
+/// The server names the response convention it verifies under with each
+/// challenge. When it differs from the client system's, the challenge handed
+/// to the prover is negated modulo `q`: `k - (q - c) * x = k + c * x`, so the
+/// prover's response then matches the server's convention.
+struct CapnpProverTransport {
+    client: auth::Client,
+    user: String,
+    convention: ResponseConvention,
+    order: BigUint,
+    auth_id: Option<String>,
+    challenge: Option<BigUint>,
+    accepted: Option<bool>,
+    session_id: Option<String>,
+    json: bool,
+}
+
+impl CapnpProverTransport {
+    fn new(client: auth::Client, system: &ZKPSystem, user: String, json: bool) -> Self {
+        Self {
+            client,
+            user,
+            convention: system.parameters().convention,
+            order: system.get_order().clone(),
+            auth_id: None,
+            challenge: None,
+            accepted: None,
+            session_id: None,
+            json,
+        }
+    }
+}
+
+impl ProverTransport for CapnpProverTransport {
+    type Error = capnp::Error;
+
+    async fn send_commitments(&mut self, commitments: (&BigUint, &BigUint)) -> Result<(), capnp::Error> {
+        let (r1, r2) = commitments;
+        let mut request = self.client.create_authentication_challenge_request();
+        let mut request_builder = request.get().init_request();
+        request_builder.set_user(&self.user);
+        request_builder.set_r1(&r1.to_bytes_be());
+        request_builder.set_r2(&r2.to_bytes_be());
+        let response = request.send().promise.await?;
+        let response_reader = response.get()?.get_response()?;
+        let auth_id = response_reader.get_auth_id()?.to_string()?;
+        let challenge = read_field(response_reader.get_c()?, "c", &self.order)?;
+        let server_convention = match response_reader.get_convention()? {
+            auth_capnp::ResponseConvention::Subtractive => ResponseConvention::Subtractive,
+            auth_capnp::ResponseConvention::Additive => ResponseConvention::Additive,
+        };
+        self.challenge = Some(if server_convention == self.convention {
+            challenge
         } else {
-            println!("Authentication failed for user: {}", user);
-            Promise::err(capnp::Error::failed("Authentication failed".to_string()))
+            (&self.order - challenge) % &self.order
+        });
+        say!(self.json, "✓ Received challenge (auth_id: {})", auth_id);
+        self.auth_id = Some(auth_id);
+        Ok(())
+    }
+
+    async fn recv_challenge(&mut self) -> Result<BigUint, capnp::Error> {
+        self.challenge
+            .take()
+            .ok_or_else(|| capnp::Error::failed("No challenge received".to_string()))
+    }
+
+    async fn send_response(&mut self, response: &BigUint) -> Result<(), capnp::Error> {
+        let auth_id = self
+            .auth_id
+            .take()
+            .ok_or_else(|| capnp::Error::failed("No authentication in progress".to_string()))?;
+        say!(self.json, "Sending authentication response...");
+        let mut request = self.client.verify_authentication_request();
+        let mut request_builder = request.get().init_request();
+        request_builder.set_auth_id(&auth_id);
+        request_builder.set_s(&response.to_bytes_be());
+        let reply = request.send().promise.await?;
+        let response_reader = reply.get()?.get_response()?;
+        let accepted = response_reader.get_accepted();
+        if accepted {
+            self.session_id = Some(response_reader.get_session_id()?.to_string()?);
         }
+        self.accepted = Some(accepted);
+        Ok(())
+    }
+
+    async fn recv_result(&mut self) -> Result<bool, capnp::Error> {
+        self.accepted
+            .take()
+            .ok_or_else(|| capnp::Error::failed("No response sent".to_string()))
+    }
+}
+
+/// Builds a `registerWithProof` request carrying the prover's public values
+/// and a proof of possession bound to `user`.
+fn register_with_proof_request(
+    client: &auth::Client,
+    system: &ZKPSystem,
+    prover: &Prover<'_, ZKPSystem>,
+    user: &str,
+) -> capnp::capability::Request<auth::register_with_proof_params::Owned, auth::register_with_proof_results::Owned> {
+    let (y1, y2) = prover.public_values_owned();
+    let ((r1, r2), k) = prover.generate_commitments();
+    let c = AuthService::possession_challenge(system.challenge_bound(), user, (&y1, &y2), (&r1, &r2));
+    let s = prover.generate_response(&c, &k);
+
+    let mut request = client.register_with_proof_request();
+    let mut request_builder = request.get().init_request();
+    request_builder.set_user(user);
+    request_builder.set_y1(&y1.to_bytes_be());
+    request_builder.set_y2(&y2.to_bytes_be());
+    request_builder.set_r1(&r1.to_bytes_be());
+    request_builder.set_r2(&r2.to_bytes_be());
+    request_builder.set_s(&s.to_bytes_be());
+    request
+}
+
+/// Serves `metrics` as `GET /metrics` on `addr`; every other request gets a 404.
+#[cfg(feature = "prometheus")]
+async fn serve_metrics(addr: String, metrics: Arc<metrics::PrometheusMetrics>) -> std::io::Result<()> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    println!("Serving metrics on http://{}/metrics", addr);
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            let mut request = [0u8; 1024];
+            let len = stream.read(&mut request).await.unwrap_or(0);
+            let response = if request[..len].starts_with(b"GET /metrics ") {
+                let body = metrics.render();
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+            };
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
     }
 }
 
+/// Connects a client to `auth_impl` over an in-memory pipe, so tests run the
+/// real Cap'n Proto RPC path without binding a port.
+///
+/// Both ends are driven with `spawn_local`, so this must be called inside a
+/// `LocalSet`. Aborting the returned server task drops the server's end of
+/// the pipe, as a lost connection would.
+#[cfg(test)]
+fn connect_in_memory(auth_impl: AuthImpl) -> (auth::Client, tokio::task::JoinHandle<()>) {
+    let (client_stream, server_stream) = tokio::io::duplex(64 * 1024);
+
+    let server_client: auth::Client = capnp_rpc::new_client(auth_impl);
+    let server_stream = tokio_util::compat::TokioAsyncReadCompatExt::compat(server_stream);
+    let (reader, writer) = futures::io::AsyncReadExt::split(server_stream);
+    let network = twoparty::VatNetwork::new(reader, writer, rpc_twoparty_capnp::Side::Server, Default::default());
+    let server_rpc = RpcSystem::new(Box::new(network), Some(server_client.client));
+    let server = tokio::task::spawn_local(async move {
+        if let Err(e) = server_rpc.await {
+            eprintln!("rpc error: {:?}", e);
+        }
+    });
+
+    let client_stream = tokio_util::compat::TokioAsyncReadCompatExt::compat(client_stream);
+    let (reader, writer) = futures::io::AsyncReadExt::split(client_stream);
+    let network = twoparty::VatNetwork::new(reader, writer, rpc_twoparty_capnp::Side::Client, Default::default());
+    let mut client_rpc = RpcSystem::new(Box::new(network), None);
+    let auth_client: auth::Client = client_rpc.bootstrap(rpc_twoparty_capnp::Side::Server);
+    tokio::task::spawn_local(async move {
+        if let Err(e) = client_rpc.await {
+            eprintln!("rpc error: {:?}", e);
+        }
+    });
+    (auth_client, server)
+}
+
+/// Opens a connection to the server at `addr` and returns its `Auth`
+/// capability.
+///
+/// The RPC system is driven with `spawn_local`, so this must be called inside
+/// a `LocalSet`.
+async fn connect_tcp(addr: &str) -> Result<auth::Client, capnp::Error> {
+    let stream = tokio::net::TcpStream::connect(addr)
+        .await
+        .map_err(|e| capnp::Error::disconnected(format!("Could not connect to {}: {}", addr, e)))?;
+    let stream = tokio_util::compat::TokioAsyncReadCompatExt::compat(stream);
+    let (reader, writer) = futures::io::AsyncReadExt::split(stream);
+    let network = twoparty::VatNetwork::new(reader, writer, rpc_twoparty_capnp::Side::Client, Default::default());
+    let mut rpc_system = RpcSystem::new(Box::new(network), None);
+    let auth_client: auth::Client = rpc_system.bootstrap(rpc_twoparty_capnp::Side::Server);
+    tokio::task::spawn_local(async move {
+        if let Err(e) = rpc_system.await {
+            eprintln!("rpc error: {:?}", e);
+        }
+    });
+    Ok(auth_client)
+}
+
+/// Bounded retries with exponential backoff for client RPCs.
+///
+/// Only transport failures (`Disconnected`, `Overloaded`) are retried.
+/// Anything the server answered, such as a rejected proof, is returned at once.
+#[derive(Debug, Clone, Copy)]
+struct RetryPolicy {
+    attempts: u32,
+    initial_backoff: Duration,
+}
+
+impl RetryPolicy {
+    const DEFAULT: Self = Self {
+        attempts: 3,
+        initial_backoff: Duration::from_millis(200),
+    };
+
+    fn is_retryable(e: &capnp::Error) -> bool {
+        matches!(e.kind, capnp::ErrorKind::Disconnected | capnp::ErrorKind::Overloaded)
+    }
+
+    /// Runs `op` until it succeeds, fails with a non-retryable error, or
+    /// `attempts` runs are used up, doubling the pause after each failure.
+    async fn run<T, F, Fut>(&self, mut op: F) -> Result<T, capnp::Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, capnp::Error>>,
+    {
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            match op().await {
+                Err(e) if attempt < self.attempts && Self::is_retryable(&e) => {
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// The `Auth` capability of a connection to the server, re-established after
+/// the connection is lost.
+///
+/// A twoparty connection that has dropped stays broken, and so does every
+/// `auth::Client` obtained from it, so retrying over the same client would
+/// fail the same way every time.
+struct Reconnecting<F> {
+    connect: F,
+    client: RefCell<Option<auth::Client>>,
+}
+
+impl<F, Fut> Reconnecting<F>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<auth::Client, capnp::Error>>,
+{
+    fn new(connect: F) -> Self {
+        Self {
+            connect,
+            client: RefCell::new(None),
+        }
+    }
+
+    /// Returns the client of the current connection, connecting first if
+    /// there is none.
+    async fn client(&self) -> Result<auth::Client, capnp::Error> {
+        let current = self.client.borrow().clone();
+        if let Some(client) = current {
+            return Ok(client);
+        }
+        let client = (self.connect)().await?;
+        *self.client.borrow_mut() = Some(client.clone());
+        Ok(client)
+    }
+
+    /// Runs `op` with the current client under `retry`, dropping the
+    /// connection after each retryable failure so the next attempt
+    /// reconnects.
+    async fn run<T, G, GFut>(&self, retry: RetryPolicy, op: G) -> Result<T, capnp::Error>
+    where
+        G: Fn(auth::Client) -> GFut,
+        GFut: Future<Output = Result<T, capnp::Error>>,
+    {
+        let op = &op;
+        retry
+            .run(move || async move {
+                let result = op(self.client().await?).await;
+                if result.as_ref().is_err_and(RetryPolicy::is_retryable) {
+                    self.client.borrow_mut().take();
+                }
+                result
+            })
+            .await
+    }
+}
+
+/// Registers `user` with the prover's public values and a proof of
+/// possession, retrying transport failures under `retry` on a fresh
+/// connection.
+async fn register<F, Fut>(
+    connection: &Reconnecting<F>,
+    system: &ZKPSystem,
+    prover: &Prover<'_, ZKPSystem>,
+    user: &str,
+    retry: RetryPolicy,
+) -> Result<(), ClientError>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<auth::Client, capnp::Error>>,
+{
+    connection
+        .run(retry, |client| register_with_proof_request(&client, system, prover, user).send().promise)
+        .await?;
+    Ok(())
+}
+
+/// Authenticates `user`, retrying transport failures under `retry` on a fresh
+/// connection.
+///
+/// Each attempt is a fresh run with new commitments, since a challenge is
+/// consumed by the server once answered.
+///
+/// # Returns
+///
+/// The session id issued by the server.
+async fn login<F, Fut>(
+    connection: &Reconnecting<F>,
+    system: &ZKPSystem,
+    prover: &Prover<'_, ZKPSystem>,
+    user: &str,
+    retry: RetryPolicy,
+    json: bool,
+) -> Result<String, ClientError>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<auth::Client, capnp::Error>>,
+{
+    let (accepted, session_id) = connection
+        .run(retry, |client| {
+            let mut transport = CapnpProverTransport::new(client, system, user.to_string(), json);
+            async move {
+                let accepted = run_prover(prover, &mut transport).await?;
+                Ok::<_, capnp::Error>((accepted, transport.session_id))
+            }
+        })
+        .await?;
+    if !accepted {
+        return Err(ClientError::AuthenticationFailed { user: user.to_string() });
+    }
+    Ok(session_id.unwrap_or_default())
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<String> = std::env::args().collect();
+    let json = args.iter().any(|arg| arg == "--json");
+    let args: Vec<String> = args.into_iter().filter(|arg| arg != "--json").collect();
     if args.len() < 2 {
         println!("Usage:");
         println!("  Server: {} server", args[0]);
-        println!("  Client: {} client <username> [register|login]", args[0]);
+        println!("  Client: {} client <username> [register|login] [--json]", args[0]);
         println!("          If action is omitted, both register and login will be performed.");
+        println!("          --json prints a single JSON object instead of the decorated output.");
+        println!("  Params: {} gen-params <bits>", args[0]);
+        println!("          Prints fresh safe-prime group parameters as hex, one `name=value` per line.");
+        println!("  The address defaults to 127.0.0.1:8080 and can be set with AUTH_SERVER_ADDR.");
+        println!("  The server verifies on AUTH_SERVER_WORKERS threads (default: one per CPU).");
+        println!("  AUTH_VERIFY_TIMEOUT_MS abandons verifications that take longer (default: no limit).");
+        println!("  AUTH_SESSION_TTL_SECS expires unanswered challenges (default: never).");
+        println!("  AUTH_MAX_SESSIONS caps pending challenges; when full, new ones are refused, or the oldest");
+        println!("          is dropped with AUTH_SESSION_EVICTION=oldest (default: no cap).");
+        println!("  AUTH_CHALLENGES_PER_USER=replace keeps one pending challenge per user, dropping the previous");
+        println!("          one; =refuse turns a second one away instead (default: no limit).");
+        println!("  With the `prometheus` feature, AUTH_METRICS_ADDR serves GET /metrics on that address.");
+        println!("  AUTH_REQUIRE_PROOF=1 makes the server refuse registrations without a proof of possession.");
+        println!("  The client seals its secret file with AUTH_SECRET_PASSPHRASE when set.");
         return Ok(());
     }
+    let addr = std::env::var("AUTH_SERVER_ADDR").unwrap_or_else(|_| "127.0.0.1:8080".to_string());
 
     let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
-    let system = Arc::new(ZKPSystem::new(p, q, alpha, beta));
+    // Built once and shared by every verification worker.
+    let system = ZKPSystem::builder()
+        .with_prime(p)
+        .with_order(q)
+        .with_generator(alpha)
+        .with_second_generator(beta)
+        .with_precomputation()
+        .build()?;
+    system.self_test()?;
+    let system = Arc::new(system);
 
     let local = tokio::task::LocalSet::new();
     
     match args[1].as_str() {
         "server" => {
             local.run_until(async move {
-                let addr = "127.0.0.1:8080";
-                let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-                let auth_impl = AuthImpl::new(system);
+                let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
+                let workers = std::env::var("AUTH_SERVER_WORKERS")
+                    .ok()
+                    .and_then(|w| w.parse().ok())
+                    .unwrap_or_else(VerifyPool::default_workers);
+                let require_proof = std::env::var("AUTH_REQUIRE_PROOF").is_ok_and(|v| v == "1");
+                let verify_timeout = std::env::var("AUTH_VERIFY_TIMEOUT_MS")
+                    .ok()
+                    .and_then(|ms| ms.parse().ok())
+                    .map(Duration::from_millis);
+                let session_ttl = std::env::var("AUTH_SESSION_TTL_SECS")
+                    .ok()
+                    .and_then(|secs| secs.parse().ok())
+                    .map(Duration::from_secs);
+                let admin_token = std::env::var("AUTH_ADMIN_TOKEN").ok().filter(|t| !t.is_empty());
+                let mut auth_impl = AuthImpl::with_workers(system, workers)
+                    .with_proof_required(require_proof)
+                    .with_verify_timeout(verify_timeout)
+                    .with_admin_token(admin_token);
+                #[cfg(feature = "prometheus")]
+                if let Ok(metrics_addr) = std::env::var("AUTH_METRICS_ADDR") {
+                    let metrics = Arc::new(metrics::PrometheusMetrics::new());
+                    auth_impl = auth_impl.with_metrics(metrics.clone());
+                    tokio::task::spawn_local(async move {
+                        if let Err(e) = serve_metrics(metrics_addr, metrics).await {
+                            eprintln!("metrics endpoint failed: {}", e);
+                        }
+                    });
+                }
+                if let Some(max) = std::env::var("AUTH_MAX_SESSIONS").ok().and_then(|m| m.parse().ok()) {
+                    let policy = match std::env::var("AUTH_SESSION_EVICTION").as_deref() {
+                        Ok("oldest") => EvictionPolicy::EvictOldest,
+                        _ => EvictionPolicy::RejectNew,
+                    };
+                    auth_impl = auth_impl.with_max_sessions(max, policy);
+                }
+                match std::env::var("AUTH_CHALLENGES_PER_USER").as_deref() {
+                    Ok("replace") => auth_impl = auth_impl.with_per_user_policy(PerUserPolicy::ReplacePrevious),
+                    Ok("refuse") => auth_impl = auth_impl.with_per_user_policy(PerUserPolicy::RefuseNew),
+                    _ => {}
+                }
+                if let Some(ttl) = session_ttl {
+                    auth_impl = auth_impl.with_session_ttl(ttl);
+                    let service = auth_impl.service.clone();
+                    tokio::task::spawn_local(async move {
+                        let mut sweep = tokio::time::interval(ttl.max(Duration::from_secs(1)));
+                        loop {
+                            sweep.tick().await;
+                            let dropped = service.gc_sessions();
+                            if dropped > 0 {
+                                println!("Dropped {} expired challenges", dropped);
+                            }
+                        }
+                    });
+                }
+                let stats_source = auth_impl.clone();
                 let auth_client: auth::Client = capnp_rpc::new_client(auth_impl);
 
                 println!("Server listening on {}", addr);
 
                 loop {
                     let (stream, _) = listener.accept().await.unwrap();
+                    let stats = stats_source.stats();
+                    println!(
+                        "Accepted connection ({} registered users, {} active sessions)",
+                        stats.registered_users, stats.active_sessions
+                    );
                     let auth_client = auth_client.clone();
                     tokio::task::spawn_local(async move {
                         let stream: tokio_util::compat::Compat<tokio::net::TcpStream> = tokio_util::compat::TokioAsyncReadCompatExt::compat(stream);
@@ -194,7 +1016,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             // Parse client arguments
             if args.len() < 3 {
                 println!("Error: Username required for client mode");
-                println!("Usage: {} client <username> [register|login]", args[0]);
+                println!("Usage: {} client <username> [register|login] [--json]", args[0]);
                 return Ok(());
             }
 
@@ -210,98 +1032,118 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 println!("Error: Invalid action '{}'. Must be 'register', 'login', or omitted for both.", action);
                 return Ok(());
             }
+            let action = action.to_string();
 
             local.run_until(async move {
-                let addr = "127.0.0.1:8080";
-                let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
-                let stream: tokio_util::compat::Compat<tokio::net::TcpStream> = tokio_util::compat::TokioAsyncReadCompatExt::compat(stream);
-                let (reader, writer) = futures::io::AsyncReadExt::split(stream);
-                let network = twoparty::VatNetwork::new(reader, writer, rpc_twoparty_capnp::Side::Client, Default::default());
-                let mut rpc_system = RpcSystem::new(Box::new(network), None);
-                let auth_client: auth::Client = rpc_system.bootstrap(rpc_twoparty_capnp::Side::Server);
-
-                tokio::task::spawn_local(async move {
-                    if let Err(e) = rpc_system.await {
-                        eprintln!("rpc error: {:?}", e);
+                let fail = |message: String| {
+                    if json {
+                        println!("{}", json_object(&[("status", "error"), ("user", &username), ("error", &message)]));
+                    } else {
+                        println!("Error: {}", message);
                     }
-                });
+                };
+
+                let connection = Reconnecting::new(|| connect_tcp(&addr));
+                if let Err(e) = connection.client().await {
+                    return fail(e.extra);
+                }
 
                 // Generate or load secret
                 let secret_file = format!(".secret_{}", username);
+                let passphrase = std::env::var("AUTH_SECRET_PASSPHRASE").ok();
                 let secret = if action == "login" {
                     // Load existing secret for login
-                    match std::fs::read_to_string(&secret_file) {
-                        Ok(secret_str) => {
-                            println!("Loading existing secret for user '{}'", username);
-                            BigUint::parse_bytes(secret_str.trim().as_bytes(), 10).unwrap()
-                        }
+                    let contents = match std::fs::read(&secret_file) {
+                        Ok(contents) => contents,
                         Err(_) => {
-                            println!("Error: No secret found for user '{}'. Please register first.", username);
-                            return;
+                            return fail(format!("No secret found for user '{}'. Please register first.", username));
+                        }
+                    };
+                    say!(json, "Loading existing secret for user '{}'", username);
+                    let secret = match &passphrase {
+                        Some(passphrase) => ZKPUtils::open_secret(&contents, passphrase).ok(),
+                        None => BigUint::parse_bytes(String::from_utf8_lossy(&contents).trim().as_bytes(), 10),
+                    };
+                    match secret {
+                        Some(secret) => secret,
+                        None => {
+                            return fail(format!(
+                                "Could not read the secret for user '{}'. Check AUTH_SECRET_PASSPHRASE.",
+                                username
+                            ));
                         }
                     }
                 } else {
                     // Generate new secret for register or both
                     let new_secret = ZKPUtils::generate_random_below(system.get_order());
                     if action == "register" || action == "both" {
-                        // Save secret to file
-                        std::fs::write(&secret_file, new_secret.to_str_radix(10)).unwrap();
-                        println!("Generated and saved secret for user '{}'", username);
+                        // Save secret to file, sealed if a passphrase is configured
+                        match &passphrase {
+                            Some(passphrase) => {
+                                std::fs::write(&secret_file, ZKPUtils::seal_secret(&new_secret, passphrase)).unwrap();
+                            }
+                            None => {
+                                say!(json, "Warning: AUTH_SECRET_PASSPHRASE is not set, storing the secret in plaintext");
+                                std::fs::write(&secret_file, new_secret.to_str_radix(10)).unwrap();
+                            }
+                        }
+                        say!(json, "Generated and saved secret for user '{}'", username);
                     }
                     new_secret
                 };
 
                 let prover = Prover::new(&*system, secret.clone());
+                let retry = RetryPolicy::DEFAULT;
 
                 // Perform registration if requested
                 if action == "register" || action == "both" {
-                    println!("\n=== Registration ===");
-                    println!("Registering user '{}'...", username);
-                    let (y1, y2) = prover.public_values();
-                    let mut request = auth_client.register_request();
-                    let mut request_builder = request.get().init_request();
-                    request_builder.set_user(&username);
-                    request_builder.set_y1(&y1.to_bytes_be());
-                    request_builder.set_y2(&y2.to_bytes_be());
-                    request.send().promise.await.unwrap();
-                    println!("✓ Registration successful for user '{}'", username);
+                    say!(json, "\n=== Registration ===");
+                    say!(json, "Registering user '{}'...", username);
+                    if let Err(e) = register(&connection, &system, &prover, &username, retry).await {
+                        return fail(format!("Registration failed: {}", e));
+                    }
+                    say!(json, "✓ Registration successful for user '{}'", username);
                 }
 
                 // Perform login if requested
+                let mut session_id = None;
                 if action == "login" || action == "both" {
-                    println!("\n=== Authentication ===");
+                    say!(json, "\n=== Authentication ===");
                     
-                    // 1. Create Challenge
-                    println!("Requesting authentication challenge for '{}'...", username);
-                    let (commitments, randomness) = prover.generate_commitments();
-                    let (r1, r2) = commitments;
-                    let mut request = auth_client.create_authentication_challenge_request();
-                    let mut request_builder = request.get().init_request();
-                    request_builder.set_user(&username);
-                    request_builder.set_r1(&r1.to_bytes_be());
-                    request_builder.set_r2(&r2.to_bytes_be());
-                    let response = request.send().promise.await.unwrap();
-                    let response_reader = response.get().unwrap().get_response().unwrap();
-                    let auth_id = response_reader.get_auth_id().unwrap().to_string().unwrap();
-                    let c_bytes = response_reader.get_c().unwrap();
-                    let c = BigUint::from_bytes_be(c_bytes);
-                    println!("✓ Received challenge (auth_id: {})", auth_id);
-
-                    // 2. Verify Authentication
-                    println!("Sending authentication response...");
-                    let s = prover.generate_response(&c, &randomness);
-                    let mut request = auth_client.verify_authentication_request();
-                    let mut request_builder = request.get().init_request();
-                    request_builder.set_auth_id(&auth_id);
-                    request_builder.set_s(&s.to_bytes_be());
-                    let response = request.send().promise.await.unwrap();
-                    let session_id = response.get().unwrap().get_response().unwrap().get_session_id().unwrap().to_string().unwrap();
-
-                    println!("✓ Authentication successful!");
-                    println!("Session ID: {}", session_id);
+                    say!(json, "Requesting authentication challenge for '{}'...", username);
+                    match login(&connection, &system, &prover, &username, retry, json).await {
+                        Ok(id) => {
+                            say!(json, "✓ Authentication successful!");
+                            say!(json, "Session ID: {}", id);
+                            session_id = Some(id);
+                        }
+                        Err(e) => return fail(e.to_string()),
+                    }
+                }
+
+                if json {
+                    let mut fields = vec![("status", "ok"), ("user", username.as_str()), ("action", action.as_str())];
+                    if let Some(id) = &session_id {
+                        fields.push(("session_id", id.as_str()));
+                    }
+                    println!("{}", json_object(&fields));
                 }
             }).await;
         }
+        "gen-params" => {
+            let bits = match args.get(2).and_then(|b| b.parse::<u64>().ok()) {
+                Some(bits) if bits >= 16 => bits,
+                _ => {
+                    println!("Usage: {} gen-params <bits>   (bits >= 16)", args[0]);
+                    return Ok(());
+                }
+            };
+            let params = ZKPUtils::generate_parameters(bits);
+            println!("p={}", params.p.to_str_radix(16));
+            println!("q={}", params.q.to_str_radix(16));
+            println!("alpha={}", params.alpha.to_str_radix(16));
+            println!("beta={}", params.beta.to_str_radix(16));
+        }
         _ => {
             println!("Unknown mode: {}", args[1]);
         }
@@ -309,3 +1151,567 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_server_stats() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let auth_impl = AuthImpl::new(Arc::new(ZKPSystem::new(p, q, alpha, beta)));
+        assert_eq!(
+            auth_impl.stats(),
+            ServerStats { registered_users: 0, active_sessions: 0 }
+        );
+
+        let prover = Prover::new(
+            auth_impl.service.system(),
+            ZKPUtils::generate_random_below(auth_impl.service.system().get_order()),
+        );
+        let (y1, y2) = prover.public_values_owned();
+        for user in ["alice", "bob"] {
+            auth_impl.service.register(user, y1.clone(), y2.clone()).unwrap();
+        }
+        let ((r1, r2), _) = prover.generate_commitments();
+        auth_impl.service.create_challenge("alice", r1, r2).unwrap();
+
+        assert_eq!(
+            auth_impl.stats(),
+            ServerStats { registered_users: 2, active_sessions: 1 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_oversized_field() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let width = p.to_bytes_be().len();
+        let auth_impl = AuthImpl::new(Arc::new(ZKPSystem::new(p, q, alpha, beta)));
+        let stats_source = auth_impl.clone();
+        let auth_client: auth::Client = capnp_rpc::new_client(auth_impl);
+
+        // One byte too wide is refused the same way as far too wide.
+        for len in [width + 1, 4096] {
+            let mut request = auth_client.register_request();
+            let mut request_builder = request.get().init_request();
+            request_builder.set_user("mallory");
+            request_builder.set_y1(&vec![0xff; len]);
+            request_builder.set_y2(&[1]);
+
+            let err = match request.send().promise.await {
+                Ok(_) => panic!("oversized y1 must be rejected"),
+                Err(e) => e,
+            };
+            let expected = format!("FieldTooLarge: `y1` is {} bytes, maximum is {}", len, width);
+            assert!(err.extra.contains(&expected));
+        }
+        assert_eq!(stats_source.stats().registered_users, 0);
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_padded_field() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = Arc::new(ZKPSystem::new(p, q, alpha, beta));
+        let prover = Prover::new(&*system, ZKPUtils::generate_random_below(system.get_order()));
+        let (y1, y2) = prover.public_values_owned();
+        let auth_impl = AuthImpl::new(system.clone());
+        let stats_source = auth_impl.clone();
+        let auth_client: auth::Client = capnp_rpc::new_client(auth_impl);
+
+        let mut padded = vec![0u8];
+        padded.extend(y1.to_bytes_be());
+        let mut request = auth_client.register_request();
+        let mut request_builder = request.get().init_request();
+        request_builder.set_user("mallory");
+        request_builder.set_y1(&padded);
+        request_builder.set_y2(&y2.to_bytes_be());
+
+        let err = match request.send().promise.await {
+            Ok(_) => panic!("zero-padded y1 must be rejected"),
+            Err(e) => e,
+        };
+        assert!(err.extra.contains("NonCanonicalEncoding"));
+        assert_eq!(stats_source.stats().registered_users, 0);
+
+        let mut request = auth_client.register_request();
+        let mut request_builder = request.get().init_request();
+        request_builder.set_user("alice");
+        request_builder.set_y1(&y1.to_bytes_be());
+        request_builder.set_y2(&y2.to_bytes_be());
+        request.send().promise.await.unwrap();
+        assert_eq!(stats_source.stats().registered_users, 1);
+    }
+
+    #[tokio::test]
+    async fn test_verify_rejects_empty_response() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = Arc::new(ZKPSystem::new(p, q, alpha, beta));
+        let auth_impl = AuthImpl::new(system.clone());
+        let stats_source = auth_impl.clone();
+        let prover = Prover::new(&*system, ZKPUtils::generate_random_below(system.get_order()));
+        let (y1, y2) = prover.public_values_owned();
+        auth_impl.service.register("alice", y1, y2).unwrap();
+        let ((r1, r2), _) = prover.generate_commitments();
+        let (auth_id, _) = auth_impl.service.create_challenge("alice", r1, r2).unwrap();
+        let auth_client: auth::Client = capnp_rpc::new_client(auth_impl);
+
+        let mut request = auth_client.verify_authentication_request();
+        let mut request_builder = request.get().init_request();
+        request_builder.set_auth_id(&auth_id);
+        request_builder.set_s(&[]);
+
+        let err = match request.send().promise.await {
+            Ok(_) => panic!("an empty s must be rejected"),
+            Err(e) => e,
+        };
+        assert!(err.extra.contains("EmptyField: `s`"));
+        // Rejected before the challenge is looked up, so it is still pending.
+        assert_eq!(stats_source.stats().active_sessions, 1);
+    }
+
+    #[tokio::test]
+    async fn test_verify_rejects_response_not_below_order() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = Arc::new(ZKPSystem::new(p, q.clone(), alpha, beta));
+        let auth_impl = AuthImpl::new(system.clone());
+        let stats_source = auth_impl.clone();
+        let prover = Prover::new(&*system, ZKPUtils::generate_random_below(system.get_order()));
+        let (y1, y2) = prover.public_values_owned();
+        auth_impl.service.register("alice", y1, y2).unwrap();
+        let ((r1, r2), _) = prover.generate_commitments();
+        let (auth_id, _) = auth_impl.service.create_challenge("alice", r1, r2).unwrap();
+        let auth_client: auth::Client = capnp_rpc::new_client(auth_impl);
+
+        // s is a scalar mod q, so q itself is out of range although it is
+        // below p.
+        let mut request = auth_client.verify_authentication_request();
+        let mut request_builder = request.get().init_request();
+        request_builder.set_auth_id(&auth_id);
+        request_builder.set_s(&q.to_bytes_be());
+
+        let err = match request.send().promise.await {
+            Ok(_) => panic!("s = q must be rejected"),
+            Err(e) => e,
+        };
+        assert!(err.extra.contains("OutOfRange: `s`"));
+        assert_eq!(stats_source.stats().active_sessions, 1);
+    }
+
+    #[tokio::test]
+    async fn test_verify_reports_rejection_as_answer() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = Arc::new(ZKPSystem::new(p, q, alpha, beta));
+        let auth_impl = AuthImpl::new(system.clone());
+        let prover = Prover::new(&*system, ZKPUtils::generate_random_below(system.get_order()));
+        let (y1, y2) = prover.public_values_owned();
+        auth_impl.service.register("alice", y1, y2).unwrap();
+        let ((r1, r2), randomness) = prover.generate_commitments();
+        let (auth_id, challenge) = auth_impl.service.create_challenge("alice", r1, r2).unwrap();
+        let auth_client: auth::Client = capnp_rpc::new_client(auth_impl);
+        let answer = |s: &BigUint| {
+            let mut request = auth_client.verify_authentication_request();
+            let mut request_builder = request.get().init_request();
+            request_builder.set_auth_id(&auth_id);
+            request_builder.set_s(&s.to_bytes_be());
+            request.send().promise
+        };
+
+        let wrong = prover.generate_response(&(&challenge + 1u32), &randomness);
+        let reply = answer(&wrong).await.expect("a rejected proof is still an answer");
+        let response = reply.get().unwrap().get_response().unwrap();
+        assert!(!response.get_accepted());
+        assert!(!response.has_session_id());
+
+        // The challenge was consumed, so there is nothing left to check.
+        let err = match answer(&wrong).await {
+            Ok(_) => panic!("an unknown auth id must be an error"),
+            Err(e) => e,
+        };
+        assert!(err.extra.contains(&ZKPError::SessionNotFound.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_field_not_below_modulus() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let modulus = p.to_bytes_be();
+        let auth_client: auth::Client = capnp_rpc::new_client(AuthImpl::new(Arc::new(ZKPSystem::new(p, q, alpha, beta))));
+
+        let mut request = auth_client.register_request();
+        let mut request_builder = request.get().init_request();
+        request_builder.set_user("mallory");
+        request_builder.set_y1(&modulus);
+        request_builder.set_y2(&[1]);
+
+        let err = match request.send().promise.await {
+            Ok(_) => panic!("y1 = p must be rejected"),
+            Err(e) => e,
+        };
+        assert!(err.extra.contains("OutOfRange: `y1`"));
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_non_subgroup_element() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let outside = (&p - 1u32).to_bytes_be();
+        let auth_impl = AuthImpl::new(Arc::new(ZKPSystem::new(p, q, alpha, beta)));
+        let stats_source = auth_impl.clone();
+        let auth_client: auth::Client = capnp_rpc::new_client(auth_impl);
+
+        let mut request = auth_client.register_request();
+        let mut request_builder = request.get().init_request();
+        request_builder.set_user("mallory");
+        request_builder.set_y1(&outside);
+        request_builder.set_y2(&[1]);
+
+        let err = match request.send().promise.await {
+            Ok(_) => panic!("y1 outside the subgroup must be rejected"),
+            Err(e) => e,
+        };
+        assert!(err.extra.contains(&ZKPError::ElementNotInSubgroup.to_string()));
+        assert_eq!(stats_source.stats().registered_users, 0);
+    }
+
+    #[tokio::test]
+    async fn test_register_with_proof() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = Arc::new(ZKPSystem::new(p, q, alpha, beta));
+        let auth_impl = AuthImpl::new(system.clone()).with_proof_required(true);
+        let stats_source = auth_impl.clone();
+        let auth_client: auth::Client = capnp_rpc::new_client(auth_impl);
+        let prover = Prover::new(&*system, ZKPUtils::generate_random_below(system.get_order()));
+
+        let mut request = auth_client.register_request();
+        let (y1, y2) = prover.public_values_owned();
+        let mut request_builder = request.get().init_request();
+        request_builder.set_user("alice");
+        request_builder.set_y1(&y1.to_bytes_be());
+        request_builder.set_y2(&y2.to_bytes_be());
+        let err = match request.send().promise.await {
+            Ok(_) => panic!("plain registration must be refused"),
+            Err(e) => e,
+        };
+        assert!(err.extra.contains(&ZKPError::ProofOfPossessionRequired.to_string()));
+
+        // A valid proof for "alice" does not carry over to another name.
+        let mut request = register_with_proof_request(&auth_client, &system, &prover, "alice");
+        request.get().get_request().unwrap().set_user("mallory");
+        let err = match request.send().promise.await {
+            Ok(_) => panic!("proof bound to another user must be rejected"),
+            Err(e) => e,
+        };
+        assert!(err.extra.contains(&ZKPError::InvalidProofOfPossession.to_string()));
+        assert_eq!(stats_source.stats().registered_users, 0);
+
+        register_with_proof_request(&auth_client, &system, &prover, "alice")
+            .send()
+            .promise
+            .await
+            .expect("valid proof must be accepted");
+        assert_eq!(stats_source.stats().registered_users, 1);
+
+        let mut transport = CapnpProverTransport::new(auth_client, &system, "alice".to_string(), true);
+        assert!(run_prover(&prover, &mut transport).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_health() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let auth_impl = AuthImpl::new(Arc::new(ZKPSystem::new(p, q, alpha, beta)));
+        let auth_client: auth::Client = capnp_rpc::new_client(auth_impl);
+
+        let reply = auth_client.health_request().send().promise.await.unwrap();
+        let response = reply.get().unwrap().get_response().unwrap();
+        assert_eq!(response.get_status().unwrap().to_str().unwrap(), "ok");
+        assert_eq!(response.get_group_bits(), 1024);
+    }
+
+    #[tokio::test]
+    async fn test_export_users_requires_admin_token() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = Arc::new(ZKPSystem::new(p, q, alpha, beta));
+        let export = |auth_impl: AuthImpl, token: &str| {
+            let auth_client: auth::Client = capnp_rpc::new_client(auth_impl);
+            let mut request = auth_client.export_users_request();
+            request.get().init_request().set_admin_token(token);
+            request.send().promise
+        };
+
+        let err = match export(AuthImpl::new(system.clone()), "").await {
+            Ok(_) => panic!("export must be refused without a configured token"),
+            Err(e) => e,
+        };
+        assert!(err.extra.contains("AdminDisabled"));
+
+        let auth_impl = AuthImpl::new(system.clone()).with_admin_token(Some("hunter2".to_string()));
+        let prover = Prover::new(&*system, ZKPUtils::generate_random_below(system.get_order()));
+        let (y1, y2) = prover.public_values_owned();
+        for user in ["bob", "alice"] {
+            auth_impl.service.register(user, y1.clone(), y2.clone()).unwrap();
+        }
+
+        let err = match export(auth_impl.clone(), "hunter3").await {
+            Ok(_) => panic!("export must be refused with a wrong token"),
+            Err(e) => e,
+        };
+        assert!(err.extra.contains("Unauthorized"));
+
+        let reply = export(auth_impl, "hunter2").await.unwrap();
+        let users = reply.get().unwrap().get_response().unwrap().get_users().unwrap();
+        assert_eq!(users.len(), 2);
+        for (entry, name) in users.iter().zip(["alice", "bob"]) {
+            assert_eq!(entry.get_user().unwrap().to_str().unwrap(), name);
+            assert_eq!(entry.get_y1().unwrap(), &y1.to_bytes_be()[..]);
+            assert_eq!(entry.get_y2().unwrap(), &y2.to_bytes_be()[..]);
+            assert!(!entry.has_label());
+        }
+    }
+
+    async fn register_and_login(auth_client: auth::Client, system: Arc<ZKPSystem>, user: String) -> bool {
+        let prover = Prover::new(&*system, ZKPUtils::generate_random_below(system.get_order()));
+        let (y1, y2) = prover.public_values_owned();
+        let mut request = auth_client.register_request();
+        let mut request_builder = request.get().init_request();
+        request_builder.set_user(&user);
+        request_builder.set_y1(&y1.to_bytes_be());
+        request_builder.set_y2(&y2.to_bytes_be());
+        request.send().promise.await.unwrap();
+
+        let mut transport = CapnpProverTransport::new(auth_client, &system, user, true);
+        run_prover(&prover, &mut transport).await.unwrap()
+    }
+
+    async fn concurrent_logins(workers: usize, logins: usize) -> std::time::Duration {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = Arc::new(ZKPSystem::new(p, q, alpha, beta));
+        let auth_client: auth::Client = capnp_rpc::new_client(AuthImpl::with_workers(system.clone(), workers));
+
+        let start = std::time::Instant::now();
+        let results = futures::future::join_all(
+            (0..logins).map(|i| register_and_login(auth_client.clone(), system.clone(), format!("user{}", i))),
+        )
+        .await;
+        assert!(results.into_iter().all(|ok| ok));
+        start.elapsed()
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_logins_through_pool() {
+        concurrent_logins(2, 16).await;
+    }
+
+    #[tokio::test]
+    async fn test_verify_with_timeout() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = Arc::new(ZKPSystem::new(p, q, alpha, beta));
+        let auth_impl = AuthImpl::new(system.clone()).with_verify_timeout(Some(Duration::from_secs(10)));
+        let prover = Prover::new(&*system, ZKPUtils::generate_random_below(system.get_order()));
+        let (y1, y2) = prover.public_values_owned();
+        auth_impl.service.register("alice", y1, y2).unwrap();
+
+        let ((r1, r2), randomness) = prover.generate_commitments();
+        let (auth_id, challenge) = auth_impl.service.create_challenge("alice", r1, r2).unwrap();
+        let s = prover.generate_response(&challenge, &randomness);
+        assert!(auth_impl.clone().verify_with_timeout(auth_id, s).await.is_ok());
+
+        let slow = auth_impl
+            .pool
+            .run_with_timeout(Duration::from_millis(20), || {
+                std::thread::sleep(Duration::from_millis(200));
+            })
+            .await;
+        assert_eq!(slow, Err(ZKPError::VerifyTimeout));
+    }
+
+    /// Connects to `auth_impl` in memory, anew on every reconnect.
+    fn in_memory_connection(
+        auth_impl: AuthImpl,
+    ) -> Reconnecting<impl Fn() -> std::future::Ready<Result<auth::Client, capnp::Error>>> {
+        Reconnecting::new(move || std::future::ready(Ok(connect_in_memory(auth_impl.clone()).0)))
+    }
+
+    #[tokio::test]
+    async fn test_client_reconnects_after_disconnect() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = Arc::new(ZKPSystem::new(p, q, alpha, beta));
+        let auth_impl = AuthImpl::new(system.clone());
+        let stats_source = auth_impl.clone();
+        let prover = Prover::new(&*system, ZKPUtils::generate_random_below(system.get_order()));
+        let policy = RetryPolicy { attempts: 3, initial_backoff: Duration::from_millis(1) };
+
+        let local = tokio::task::LocalSet::new();
+        local
+            .run_until(async {
+                let servers = RefCell::new(Vec::new());
+                let connection = Reconnecting::new(|| {
+                    let (client, server) = connect_in_memory(auth_impl.clone());
+                    servers.borrow_mut().push(server);
+                    async move { Ok::<_, capnp::Error>(client) }
+                });
+                register(&connection, &system, &prover, "alice", policy).await.unwrap();
+                login(&connection, &system, &prover, "alice", policy, true).await.unwrap();
+
+                // Drop the server's end of the pipe, so the connection is
+                // really gone rather than failing one call.
+                servers.borrow()[0].abort();
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                let stale = connection.client().await.unwrap();
+                match stale.health_request().send().promise.await {
+                    Ok(_) => panic!("the dropped connection must stay broken"),
+                    Err(e) => assert!(RetryPolicy::is_retryable(&e)),
+                }
+
+                // Registering again with a fresh proof replaces the same
+                // public values, so a retried registration is harmless.
+                register(&connection, &system, &prover, "alice", policy)
+                    .await
+                    .expect("registration must reconnect after a disconnect");
+                assert_eq!(stats_source.stats().registered_users, 1);
+                assert_eq!(servers.borrow().len(), 2);
+
+                servers.borrow()[1].abort();
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                let session_id = login(&connection, &system, &prover, "alice", policy, true)
+                    .await
+                    .expect("login must reconnect after a disconnect");
+                assert!(!session_id.is_empty());
+                assert_eq!(servers.borrow().len(), 3);
+            })
+            .await;
+
+        // A definitive answer from the server is not retried.
+        let calls = Cell::new(0);
+        let result: Result<(), capnp::Error> = policy
+            .run(|| {
+                calls.set(calls.get() + 1);
+                async { Err(capnp::Error::from(ZKPError::AuthenticationFailed)) }
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_register_and_login_in_memory() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = Arc::new(ZKPSystem::new(p, q, alpha, beta));
+        let auth_impl = AuthImpl::new(system.clone());
+        let stats_source = auth_impl.clone();
+
+        let local = tokio::task::LocalSet::new();
+        local
+            .run_until(async move {
+                let (auth_client, _server) = connect_in_memory(auth_impl);
+                assert!(register_and_login(auth_client, system, "alice".to_string()).await);
+            })
+            .await;
+        assert_eq!(stats_source.stats().registered_users, 1);
+    }
+
+    #[tokio::test]
+    async fn test_client_helpers_return_errors() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = Arc::new(ZKPSystem::new(p, q, alpha, beta));
+        let auth_impl = AuthImpl::new(system.clone());
+
+        let local = tokio::task::LocalSet::new();
+        local
+            .run_until(async move {
+                let connection = in_memory_connection(auth_impl);
+                let retry = RetryPolicy::DEFAULT;
+                let prover = Prover::new(&*system, ZKPUtils::generate_random_below(system.get_order()));
+                let impostor = Prover::new(&*system, ZKPUtils::generate_random_below(system.get_order()));
+
+                match login(&connection, &system, &prover, "alice", retry, true).await {
+                    Err(ClientError::Rpc(e)) => assert!(e.extra.contains("User not found")),
+                    other => panic!("login before registering must fail, got {:?}", other),
+                }
+
+                register(&connection, &system, &prover, "alice", retry).await.unwrap();
+                match login(&connection, &system, &impostor, "alice", retry, true).await {
+                    Err(ClientError::AuthenticationFailed { user }) => assert_eq!(user, "alice"),
+                    other => panic!("a wrong secret must be rejected, got {:?}", other),
+                }
+
+                let session_id = login(&connection, &system, &prover, "alice", retry, true).await.unwrap();
+                assert!(!session_id.is_empty());
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_client_adapts_to_server_convention() {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let client_system = Arc::new(ZKPSystem::new(p, q, alpha, beta));
+        let server_system = ZKPSystem::builder_from(client_system.parameters())
+            .with_response_convention(ResponseConvention::Additive)
+            .build()
+            .unwrap();
+        let auth_impl = AuthImpl::new(Arc::new(server_system));
+        let prover = Prover::new(&*client_system, ZKPUtils::generate_random_below(client_system.get_order()));
+        let (y1, y2) = prover.public_values_owned();
+        auth_impl.service.register("alice", y1, y2).unwrap();
+
+        let local = tokio::task::LocalSet::new();
+        local
+            .run_until(async {
+                let connection = in_memory_connection(auth_impl);
+                let session_id = login(&connection, &client_system, &prover, "alice", RetryPolicy::DEFAULT, true)
+                    .await
+                    .expect("the client must answer under the server's convention");
+                assert!(!session_id.is_empty());
+            })
+            .await;
+    }
+
+    /// Answers `count` challenges prepared in advance over RPC at once and
+    /// returns how long the server took to check them all.
+    ///
+    /// The responses are computed before the clock starts, so only the
+    /// server's verification on the pool is timed.
+    async fn concurrent_verifications(workers: usize, count: usize) -> std::time::Duration {
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        let system = Arc::new(ZKPSystem::new(p, q, alpha, beta));
+        let auth_impl = AuthImpl::with_workers(system.clone(), workers);
+        let prover = Prover::new(&*system, ZKPUtils::generate_random_below(system.get_order()));
+        let (y1, y2) = prover.public_values_owned();
+        auth_impl.service.register("alice", y1, y2).unwrap();
+        let answers: Vec<(String, BigUint)> = (0..count)
+            .map(|_| {
+                let ((r1, r2), randomness) = prover.generate_commitments();
+                let (auth_id, challenge) = auth_impl.service.create_challenge("alice", r1, r2).unwrap();
+                (auth_id, prover.generate_response(&challenge, &randomness))
+            })
+            .collect();
+        let auth_client: auth::Client = capnp_rpc::new_client(auth_impl);
+
+        let start = std::time::Instant::now();
+        let replies = futures::future::join_all(answers.iter().map(|(auth_id, s)| {
+            let mut request = auth_client.verify_authentication_request();
+            let mut request_builder = request.get().init_request();
+            request_builder.set_auth_id(auth_id);
+            request_builder.set_s(&s.to_bytes_be());
+            request.send().promise
+        }))
+        .await;
+        let elapsed = start.elapsed();
+        for reply in replies {
+            assert!(reply.unwrap().get().unwrap().get_response().unwrap().get_accepted());
+        }
+        elapsed
+    }
+
+    /// Timing-based, so not run by default: `cargo test -- --ignored`.
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore]
+    async fn load_test_throughput_scales_with_workers() {
+        let cores = VerifyPool::default_workers();
+        if cores < 2 {
+            return;
+        }
+        let single = concurrent_verifications(1, 64).await;
+        let parallel = concurrent_verifications(cores, 64).await;
+        println!("1 worker: {:?}, {} workers: {:?}", single, cores, parallel);
+        assert!(parallel < single);
+    }
+}
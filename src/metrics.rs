@@ -0,0 +1,146 @@
+use std::time::Duration;
+
+#[cfg(feature = "prometheus")]
+use std::fmt::Write;
+#[cfg(feature = "prometheus")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Receives events from an `AuthService` for monitoring.
+///
+/// Every method defaults to doing nothing, so a sink only implements the
+/// events it cares about. Methods are called on the request path and should
+/// not block.
+pub trait MetricsSink: Send + Sync {
+    /// A user was registered.
+    fn registration(&self) {}
+
+    /// A challenge was issued.
+    fn challenge_issued(&self) {}
+
+    /// A response was checked; `success` is `true` if it was accepted.
+    fn verification(&self, success: bool, latency: Duration) {
+        let _ = (success, latency);
+    }
+}
+
+/// A sink that discards every event.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopMetrics;
+
+impl MetricsSink for NoopMetrics {}
+
+/// Upper bounds, in seconds, of the verify latency histogram buckets.
+#[cfg(feature = "prometheus")]
+const LATENCY_BUCKETS: [f64; 9] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0];
+
+/// A sink that keeps counters and a latency histogram and renders them in the
+/// Prometheus text exposition format.
+#[cfg(feature = "prometheus")]
+#[derive(Debug, Default)]
+pub struct PrometheusMetrics {
+    registrations: AtomicU64,
+    challenges: AtomicU64,
+    verify_success: AtomicU64,
+    verify_failure: AtomicU64,
+    // one counter per bucket in LATENCY_BUCKETS, then +Inf; not cumulative
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    latency_sum_micros: AtomicU64,
+}
+
+#[cfg(feature = "prometheus")]
+impl PrometheusMetrics {
+    /// Creates a sink with every metric at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Renders all metrics in the Prometheus text format (version 0.0.4).
+    pub fn render(&self) -> String {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        let mut out = String::new();
+
+        for (name, help, value) in [
+            (
+                "auth_registrations_total",
+                "Users registered.",
+                &self.registrations,
+            ),
+            (
+                "auth_challenges_total",
+                "Challenges issued.",
+                &self.challenges,
+            ),
+        ] {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, load(value));
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP auth_verifications_total Responses checked, by outcome."
+        );
+        let _ = writeln!(out, "# TYPE auth_verifications_total counter");
+        let _ = writeln!(
+            out,
+            "auth_verifications_total{{outcome=\"success\"}} {}",
+            load(&self.verify_success)
+        );
+        let _ = writeln!(
+            out,
+            "auth_verifications_total{{outcome=\"failure\"}} {}",
+            load(&self.verify_failure)
+        );
+
+        let _ = writeln!(
+            out,
+            "# HELP auth_verify_latency_seconds Time spent checking a response."
+        );
+        let _ = writeln!(out, "# TYPE auth_verify_latency_seconds histogram");
+        let mut cumulative = 0;
+        for (i, bucket) in self.latency_buckets.iter().enumerate() {
+            cumulative += load(bucket);
+            let le = LATENCY_BUCKETS
+                .get(i)
+                .map_or_else(|| "+Inf".to_string(), |bound| bound.to_string());
+            let _ = writeln!(
+                out,
+                "auth_verify_latency_seconds_bucket{{le=\"{}\"}} {}",
+                le, cumulative
+            );
+        }
+        let sum = load(&self.latency_sum_micros) as f64 / 1e6;
+        let _ = writeln!(out, "auth_verify_latency_seconds_sum {}", sum);
+        let _ = writeln!(out, "auth_verify_latency_seconds_count {}", cumulative);
+        out
+    }
+}
+
+#[cfg(feature = "prometheus")]
+impl MetricsSink for PrometheusMetrics {
+    fn registration(&self) {
+        self.registrations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn challenge_issued(&self) {
+        self.challenges.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn verification(&self, success: bool, latency: Duration) {
+        let outcome = if success {
+            &self.verify_success
+        } else {
+            &self.verify_failure
+        };
+        outcome.fetch_add(1, Ordering::Relaxed);
+
+        let seconds = latency.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.latency_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.latency_sum_micros
+            .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+    }
+}
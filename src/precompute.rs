@@ -0,0 +1,143 @@
+use num_bigint::BigUint;
+
+#[cfg(test)]
+thread_local! {
+    // Per thread, so tests running in parallel do not see each other's builds.
+    static BUILDS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    static POWS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Precomputed powers of a fixed base for fast modular exponentiation.
+///
+/// The exponent is split into `window`-bit digits `d_j`, and the table holds
+/// `base^(d * 2^(window * j)) mod modulus` for every nonzero digit `d` and
+/// every digit position `j`. An exponentiation then needs one multiplication
+/// per nonzero digit and no squarings.
+///
+/// Wider windows mean fewer multiplications but a table that grows as
+/// `2^window`; see `window_for_modulus_bits` for the defaults.
+#[derive(Debug, Clone)]
+pub struct FixedBaseTable {
+    base: BigUint,
+    modulus: BigUint,
+    window: u32,
+    // rows[j][d - 1] = base^(d * 2^(window * j)) mod modulus
+    rows: Vec<Vec<BigUint>>,
+}
+
+impl FixedBaseTable {
+    /// Widest window accepted by `with_window`.
+    pub const MAX_WINDOW: u32 = 16;
+
+    /// Returns the default window width for a modulus of `bits` bits.
+    ///
+    /// The widths were picked with `cargo bench --bench precompute`: 4 up to
+    /// 1024 bits, 5 up to 2048 bits and 6 above. Wider windows keep getting
+    /// slightly faster, but each extra bit roughly doubles the table, and
+    /// past these widths the saving no longer pays for the memory and the
+    /// build time.
+    pub fn window_for_modulus_bits(bits: u64) -> u32 {
+        match bits {
+            0..=1024 => 4,
+            1025..=2048 => 5,
+            _ => 6,
+        }
+    }
+
+    /// Builds a table for `base` with the default window for `modulus`.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The fixed base.
+    /// * `modulus` - The modulus; must not be zero.
+    /// * `max_exponent_bits` - The longest exponent the table covers. Longer
+    ///   exponents still work but fall back to `modpow`.
+    pub fn new(base: &BigUint, modulus: &BigUint, max_exponent_bits: u64) -> Self {
+        let window = Self::window_for_modulus_bits(modulus.bits());
+        Self::with_window(base, modulus, max_exponent_bits, window)
+    }
+
+    /// Builds a table for `base` with an explicit window width.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `modulus` is zero or `window` is not in `1..=MAX_WINDOW`.
+    pub fn with_window(
+        base: &BigUint,
+        modulus: &BigUint,
+        max_exponent_bits: u64,
+        window: u32,
+    ) -> Self {
+        assert!(*modulus != BigUint::from(0u32), "modulus must not be zero");
+        assert!(
+            (1..=Self::MAX_WINDOW).contains(&window),
+            "window must be between 1 and {}",
+            Self::MAX_WINDOW
+        );
+
+        #[cfg(test)]
+        BUILDS.with(|builds| builds.set(builds.get() + 1));
+
+        let positions = max_exponent_bits.div_ceil(u64::from(window));
+        let mut rows = Vec::with_capacity(positions as usize);
+        let mut row_base = base % modulus;
+        for _ in 0..positions {
+            let mut row = Vec::with_capacity((1usize << window) - 1);
+            let mut power = row_base.clone();
+            for _ in 1..(1u32 << window) {
+                row.push(power.clone());
+                power = power * &row_base % modulus;
+            }
+            // `power` is now row_base^(2^window), the base of the next row.
+            row_base = power;
+            rows.push(row);
+        }
+
+        Self {
+            base: base % modulus,
+            modulus: modulus.clone(),
+            window,
+            rows,
+        }
+    }
+
+    /// Returns how many tables the current thread has built, so tests can
+    /// tell whether an operation built new tables.
+    #[cfg(test)]
+    pub(crate) fn builds_on_this_thread() -> usize {
+        BUILDS.with(|builds| builds.get())
+    }
+
+    /// Returns how many exponentiations the current thread has run through
+    /// any table, so tests can tell whether an operation exponentiated at all.
+    #[cfg(test)]
+    pub(crate) fn pows_on_this_thread() -> usize {
+        POWS.with(|pows| pows.get())
+    }
+
+    /// Returns the window width the table was built with.
+    pub fn window(&self) -> u32 {
+        self.window
+    }
+
+    /// Computes `base^exponent mod modulus`.
+    pub fn pow(&self, exponent: &BigUint) -> BigUint {
+        #[cfg(test)]
+        POWS.with(|pows| pows.set(pows.get() + 1));
+        let window = u64::from(self.window);
+        if exponent.bits() > self.rows.len() as u64 * window {
+            return self.base.modpow(exponent, &self.modulus);
+        }
+
+        let mut acc = BigUint::from(1u32) % &self.modulus;
+        for (j, row) in self.rows.iter().enumerate() {
+            let digit = (0..window)
+                .filter(|b| exponent.bit(j as u64 * window + b))
+                .fold(0usize, |d, b| d | (1 << b));
+            if digit != 0 {
+                acc = acc * &row[digit - 1] % &self.modulus;
+            }
+        }
+        acc
+    }
+}
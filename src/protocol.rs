@@ -1,3 +1,4 @@
+use crate::actors::ProofInput;
 use num_bigint::BigUint;
 
 /// Defines the interface for a Zero-Knowledge Proof protocol.
@@ -74,4 +75,61 @@ pub trait ZKPProtocol {
     ///
     /// A reference to the order `q`.
     fn get_order(&self) -> &BigUint;
+
+    /// Checks the two verification equations separately.
+    ///
+    /// # Returns
+    ///
+    /// `(first, second)`: whether the `alpha`/`y1`/`r1` equation and the
+    /// `beta`/`y2`/`r2` equation hold. The default cannot tell them apart and
+    /// reports the result of `verify` for both.
+    fn verify_equations(
+        &self,
+        commitments: (&BigUint, &BigUint),
+        challenge: &BigUint,
+        response: &BigUint,
+        public_keys: (&BigUint, &BigUint),
+    ) -> (bool, bool) {
+        let is_valid = self.verify(commitments, challenge, response, public_keys);
+        (is_valid, is_valid)
+    }
+
+    /// Verifies several proofs, returning one result per proof in order.
+    ///
+    /// The default checks each proof with `verify`. Implementations may share
+    /// work between proofs, e.g. reuse `y^c` for repeated `(y, c)` pairs.
+    fn verify_batch(&self, proofs: &[ProofInput]) -> Vec<bool> {
+        proofs
+            .iter()
+            .map(|proof| {
+                let (r1, r2) = &proof.commitments;
+                let (y1, y2) = &proof.public_values;
+                self.verify((r1, r2), &proof.challenge, &proof.response, (y1, y2))
+            })
+            .collect()
+    }
+
+    /// Returns bytes identifying the group, bound into Fiat-Shamir challenges.
+    ///
+    /// Defaults to empty, which binds nothing beyond the proof values.
+    fn hash_domain(&self) -> Vec<u8> {
+        Vec::new()
+    }
+
+    /// Returns the exclusive upper bound on challenges.
+    ///
+    /// Defaults to the group order `q`; parameter sets with a shorter
+    /// challenge space return a smaller bound.
+    fn challenge_bound(&self) -> &BigUint {
+        self.get_order()
+    }
+
+    /// Returns `true` if `value` is an element of the prime-order group.
+    ///
+    /// Defaults to accepting everything; implementations over a subgroup
+    /// should override it so verifiers can reject small-subgroup inputs.
+    fn is_group_element(&self, value: &BigUint) -> bool {
+        let _ = value;
+        true
+    }
 }
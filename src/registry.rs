@@ -0,0 +1,91 @@
+use crate::error::ZKPError;
+use crate::system::{ResponseConvention, ZKPParameters};
+use crate::utils::ZKPUtils;
+use num_bigint::BigUint;
+use std::collections::HashMap;
+
+/// Maps group identifiers to parameters, so both sides can agree on a group
+/// by exchanging only its id.
+///
+/// A new registry contains the built-in groups:
+/// - `"modp-1024"`: RFC 5114 1024-bit MODP group with 160-bit subgroup.
+/// - `"modp-2048"`: RFC 5114 2048-bit MODP group with 224-bit subgroup.
+#[derive(Debug, Clone)]
+pub struct GroupRegistry {
+    groups: HashMap<String, ZKPParameters>,
+}
+
+impl GroupRegistry {
+    /// Creates a registry containing the built-in groups.
+    pub fn new() -> Self {
+        let mut groups = HashMap::new();
+
+        let (alpha, beta, p, q) = ZKPUtils::get_1024_bit_constants();
+        groups.insert(
+            "modp-1024".to_string(),
+            ZKPParameters {
+                p,
+                q,
+                alpha,
+                beta,
+                convention: ResponseConvention::default(),
+                c_max: None,
+            },
+        );
+
+        // `get_2048_bit_constants` draws beta at random; a shared group needs
+        // the same beta on both sides, so derive it from a fixed exponent.
+        let (alpha, _, p, q) = ZKPUtils::get_2048_bit_constants();
+        let exp = BigUint::from_bytes_be(&hex::decode("3C8F9D7E1A2B4C6D8E0F1A3B").unwrap());
+        let beta = alpha.modpow(&exp, &p);
+        groups.insert(
+            "modp-2048".to_string(),
+            ZKPParameters {
+                p,
+                q,
+                alpha,
+                beta,
+                convention: ResponseConvention::default(),
+                c_max: None,
+            },
+        );
+
+        Self { groups }
+    }
+
+    /// Returns the parameters registered under `id`, if any.
+    pub fn get(&self, id: &str) -> Option<&ZKPParameters> {
+        self.groups.get(id)
+    }
+
+    /// Registers a custom group under `id`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the id was free.
+    /// * `Err(ZKPError::DuplicateGroupId)` if `id` is already registered;
+    ///   existing groups, including the built-ins, are never replaced.
+    pub fn register(
+        &mut self,
+        id: impl Into<String>,
+        params: ZKPParameters,
+    ) -> Result<(), ZKPError> {
+        let id = id.into();
+        if self.groups.contains_key(&id) {
+            return Err(ZKPError::DuplicateGroupId(id));
+        }
+        self.groups.insert(id, params);
+        Ok(())
+    }
+
+    /// Returns the registered ids, in no particular order.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.groups.keys().map(String::as_str)
+    }
+}
+
+impl Default for GroupRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,86 @@
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+
+/// A bounded, least-recently-used cache of proof fingerprints.
+///
+/// Lets a verifier without per-session state reject exact replays of a
+/// transcript `(r1, r2, s)` it has recently accepted. Once `capacity`
+/// fingerprints are held, the least recently seen one is evicted.
+#[derive(Debug)]
+pub struct ReplayCache {
+    capacity: usize,
+    tick: u64,
+    // fingerprint -> tick of last use
+    entries: HashMap<[u8; 32], u64>,
+    // tick of last use -> fingerprint, oldest first
+    order: BTreeMap<u64, [u8; 32]>,
+}
+
+impl ReplayCache {
+    /// Creates an empty cache holding at most `capacity` fingerprints.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "replay cache capacity must be non-zero");
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of fingerprints currently held.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the cache holds no fingerprints.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Computes the fingerprint of a transcript.
+    pub fn fingerprint(commitments: (&BigUint, &BigUint), response: &BigUint) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        for value in [commitments.0, commitments.1, response] {
+            let bytes = value.to_bytes_be();
+            hasher.update((bytes.len() as u32).to_be_bytes());
+            hasher.update(&bytes);
+        }
+        hasher.finalize().into()
+    }
+
+    /// Checks whether `fingerprint` has been seen, marking it as recently used.
+    pub fn contains(&mut self, fingerprint: &[u8; 32]) -> bool {
+        match self.entries.get(fingerprint).copied() {
+            Some(last_used) => {
+                self.order.remove(&last_used);
+                self.touch(*fingerprint);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Records `fingerprint`, evicting the least recently used entry if full.
+    pub fn insert(&mut self, fingerprint: [u8; 32]) {
+        if let Some(last_used) = self.entries.get(&fingerprint).copied() {
+            self.order.remove(&last_used);
+        } else if self.entries.len() == self.capacity {
+            if let Some((_, oldest)) = self.order.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.touch(fingerprint);
+    }
+
+    fn touch(&mut self, fingerprint: [u8; 32]) {
+        self.tick += 1;
+        self.entries.insert(fingerprint, self.tick);
+        self.order.insert(self.tick, fingerprint);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::protocol::ZKPProtocol;
+use num_bigint::BigUint;
+
+/// Performs the operations that need the secret `x`.
+///
+/// Implementations backed by an HSM or OS keystore can keep `x` out of
+/// process memory entirely; the `Prover` only ever asks for the public
+/// values and for responses to challenges.
+pub trait SecretProvider {
+    /// Computes the public values (y1, y2) for the held secret.
+    fn public_values<T: ZKPProtocol>(&self, system: &T) -> (BigUint, BigUint);
+
+    /// Computes the response `s = k - c * x mod q` for the held secret.
+    ///
+    /// # Arguments
+    ///
+    /// * `system` - The ZKP system in use.
+    /// * `randomness` - The random value `k` used in the commitment step.
+    /// * `challenge` - The challenge `c` received from the verifier.
+    fn respond<T: ZKPProtocol>(
+        &self,
+        system: &T,
+        randomness: &BigUint,
+        challenge: &BigUint,
+    ) -> BigUint;
+
+    /// Returns the provider with its secret reduced modulo `q`.
+    ///
+    /// Called once when a `Prover` takes the provider. Defaults to returning
+    /// it unchanged, which suits providers that cannot expose the secret.
+    fn reduce<T: ZKPProtocol>(self, system: &T) -> Self
+    where
+        Self: Sized,
+    {
+        let _ = system;
+        self
+    }
+}
+
+/// The default, in-memory provider: the secret is held as a plain `BigUint`.
+impl SecretProvider for BigUint {
+    fn public_values<T: ZKPProtocol>(&self, system: &T) -> (BigUint, BigUint) {
+        system.compute_public_values(self)
+    }
+
+    fn respond<T: ZKPProtocol>(
+        &self,
+        system: &T,
+        randomness: &BigUint,
+        challenge: &BigUint,
+    ) -> BigUint {
+        system.compute_response(randomness, challenge, self)
+    }
+
+    fn reduce<T: ZKPProtocol>(self, system: &T) -> Self {
+        self % system.get_order()
+    }
+}
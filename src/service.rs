@@ -0,0 +1,467 @@
+use crate::actors::Verifier;
+use crate::audit::{AuditEvent, AuthAuditor, NoopAuditor};
+use crate::error::{VerifierError, ZKPError};
+use crate::group::GroupElement;
+use crate::metrics::{MetricsSink, NoopMetrics};
+use crate::protocol::ZKPProtocol;
+use crate::session::{EvictionPolicy, PerUserPolicy, SessionManager};
+use crate::system::ZKPSystem;
+use crate::transport::PublicValueStore;
+use crate::utils::ZKPUtils;
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// What the service stores for a registered user.
+///
+/// Only `y1` and `y2` take part in verification; the rest is metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistrationRecord {
+    /// The public value `y1 = alpha^x mod p`.
+    pub y1: BigUint,
+    /// The public value `y2 = beta^x mod p`.
+    pub y2: BigUint,
+    /// When the user registered, by the wall clock (`SystemTime`) of the
+    /// server. Set by `RegistrationRecord::new`.
+    pub registered_at: SystemTime,
+    /// A free-form label, e.g. the device the secret lives on; set with
+    /// `with_label`.
+    pub label: Option<String>,
+}
+
+impl RegistrationRecord {
+    /// Creates a record registered now, without a label.
+    pub fn new(y1: BigUint, y2: BigUint) -> Self {
+        Self {
+            y1,
+            y2,
+            registered_at: SystemTime::now(),
+            label: None,
+        }
+    }
+
+    /// Attaches a free-form label, e.g. the device the secret lives on.
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+}
+
+/// Serialized as `{"y1": hex, "y2": hex, "registered_at": unix seconds,
+/// "label": string or null}`, e.g. for `AuthService::export_users` backups.
+#[cfg(feature = "serde")]
+mod record_serde {
+    use super::RegistrationRecord;
+    use num_bigint::BigUint;
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[derive(Serialize, Deserialize)]
+    struct RecordFields {
+        y1: String,
+        y2: String,
+        registered_at: u64,
+        label: Option<String>,
+    }
+
+    impl Serialize for RegistrationRecord {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let registered_at = self
+                .registered_at
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs());
+            RecordFields {
+                y1: self.y1.to_str_radix(16),
+                y2: self.y2.to_str_radix(16),
+                registered_at,
+                label: self.label.clone(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for RegistrationRecord {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let fields = RecordFields::deserialize(deserializer)?;
+            let parse = |name: &str, value: &str| {
+                BigUint::parse_bytes(value.as_bytes(), 16)
+                    .ok_or_else(|| D::Error::custom(format!("`{}` is not a hex number", name)))
+            };
+            Ok(RegistrationRecord {
+                y1: parse("y1", &fields.y1)?,
+                y2: parse("y2", &fields.y2)?,
+                registered_at: UNIX_EPOCH + Duration::from_secs(fields.registered_at),
+                label: fields.label,
+            })
+        }
+    }
+}
+
+/// Draws a fresh challenge for `create_challenge`.
+type ChallengeGenerator = Arc<dyn Fn() -> BigUint + Send + Sync>;
+
+/// The server-side authentication logic, independent of any transport.
+///
+/// Holds the registered public values and the outstanding challenges. All
+/// state is shared behind `Arc`s, so clones operate on the same service and
+/// can be moved to other threads.
+#[derive(Clone)]
+pub struct AuthService {
+    system: Arc<ZKPSystem>,
+    generate_challenge: ChallengeGenerator,
+    require_proof: bool,
+    users: Arc<Mutex<HashMap<String, RegistrationRecord>>>,
+    sessions: Arc<SessionManager>,
+    metrics: Arc<dyn MetricsSink>,
+    auditor: Arc<dyn AuthAuditor>,
+}
+
+impl AuthService {
+    /// Creates a service with no registered users.
+    pub fn new(system: Arc<ZKPSystem>) -> Self {
+        let challenge_system = system.clone();
+        Self {
+            system,
+            generate_challenge: Arc::new(move || {
+                Verifier::new(&*challenge_system).generate_challenge()
+            }),
+            require_proof: false,
+            users: Arc::new(Mutex::new(HashMap::new())),
+            sessions: Arc::new(SessionManager::new()),
+            metrics: Arc::new(NoopMetrics),
+            auditor: Arc::new(NoopAuditor),
+        }
+    }
+
+    /// Replaces the source of challenges, e.g. to make them deterministic in tests.
+    ///
+    /// The generator must be able to produce more than one value, since a
+    /// draw that collides with a live challenge of the same user is retried.
+    pub fn with_challenge_generator(
+        mut self,
+        generator: impl Fn() -> BigUint + Send + Sync + 'static,
+    ) -> Self {
+        self.generate_challenge = Arc::new(generator);
+        self
+    }
+
+    /// Makes `register` refuse registrations, so that every user has to go
+    /// through `register_with_proof`.
+    pub fn with_proof_required(mut self, required: bool) -> Self {
+        self.require_proof = required;
+        self
+    }
+
+    /// Makes challenges expire `ttl` after being issued.
+    ///
+    /// Replaces the session store, so call it before issuing challenges.
+    pub fn with_session_ttl(mut self, ttl: Duration) -> Self {
+        let sessions = &self.sessions;
+        self.sessions = Self::new_sessions(
+            Some(ttl),
+            sessions.max_sessions(),
+            sessions.per_user_policy(),
+        );
+        self
+    }
+
+    /// Holds at most `max` pending challenges, applying `policy` when full;
+    /// see `SessionManager::with_max_sessions`.
+    ///
+    /// Replaces the session store, so call it before issuing challenges.
+    pub fn with_max_sessions(mut self, max: usize, policy: EvictionPolicy) -> Self {
+        let sessions = &self.sessions;
+        self.sessions = Self::new_sessions(
+            sessions.ttl(),
+            Some((max, policy)),
+            sessions.per_user_policy(),
+        );
+        self
+    }
+
+    /// Limits how many challenges each user may have pending, e.g. to one
+    /// so that a new challenge invalidates the previous one; see
+    /// `PerUserPolicy`.
+    ///
+    /// Replaces the session store, so call it before issuing challenges.
+    pub fn with_per_user_policy(mut self, policy: PerUserPolicy) -> Self {
+        let sessions = &self.sessions;
+        self.sessions = Self::new_sessions(sessions.ttl(), sessions.max_sessions(), policy);
+        self
+    }
+
+    /// Reports registrations, challenges and verifications to `sink`.
+    pub fn with_metrics(mut self, sink: Arc<dyn MetricsSink>) -> Self {
+        self.metrics = sink;
+        self
+    }
+
+    /// Reports every registration, challenge and verification decision to
+    /// `auditor`.
+    ///
+    /// Responses naming an unknown, used or expired challenge are not
+    /// reported, since nothing ties them to a user.
+    pub fn with_auditor(mut self, auditor: Arc<dyn AuthAuditor>) -> Self {
+        self.auditor = auditor;
+        self
+    }
+
+    /// Returns the ZKP system the service verifies against.
+    pub fn system(&self) -> &ZKPSystem {
+        &self.system
+    }
+
+    /// Registers `user` with public values (y1, y2), replacing any previous ones.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` on success.
+    /// * `Err(ZKPError::ProofOfPossessionRequired)` if the service requires
+    ///   `register_with_proof`.
+    /// * `Err(ZKPError)` if either value is not a subgroup element.
+    pub fn register(&self, user: &str, y1: BigUint, y2: BigUint) -> Result<(), ZKPError> {
+        self.register_record(user, RegistrationRecord::new(y1, y2))
+    }
+
+    /// Registers `user` with a full record, keeping its metadata.
+    ///
+    /// # Returns
+    ///
+    /// The same as `register`.
+    pub fn register_record(&self, user: &str, record: RegistrationRecord) -> Result<(), ZKPError> {
+        if self.require_proof {
+            return Err(ZKPError::ProofOfPossessionRequired);
+        }
+        self.store_user(user, record)
+    }
+
+    /// Registers `user` after checking a proof that the caller knows the
+    /// secret behind (y1, y2).
+    ///
+    /// The proof is a non-interactive Chaum-Pedersen proof whose challenge is
+    /// `possession_challenge`, so it is bound to `user` and cannot be replayed
+    /// to register the same values under another name.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The name to register.
+    /// * `y1`, `y2` - The public values.
+    /// * `commitments` - The commitments (r1, r2) of the proof.
+    /// * `response` - The response `s` of the proof.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the proof verifies and the user is stored.
+    /// * `Err(ZKPError::InvalidProofOfPossession)` if the proof is invalid.
+    /// * `Err(ZKPError)` if either public value is not a subgroup element.
+    pub fn register_with_proof(
+        &self,
+        user: &str,
+        y1: BigUint,
+        y2: BigUint,
+        commitments: (BigUint, BigUint),
+        response: &BigUint,
+    ) -> Result<(), ZKPError> {
+        let (r1, r2) = &commitments;
+        let challenge =
+            Self::possession_challenge(self.system.challenge_bound(), user, (&y1, &y2), (r1, r2));
+        if !self
+            .system
+            .verify_registration_proof(&y1, &y2, (r1, r2), &challenge, response)
+        {
+            return Err(ZKPError::InvalidProofOfPossession);
+        }
+        self.store_user(user, RegistrationRecord::new(y1, y2))
+    }
+
+    /// Derives the challenge of a proof of possession for `register_with_proof`.
+    ///
+    /// `c = SHA-256("register" || user || y1 || y2 || r1 || r2) mod order`, with
+    /// every field prefixed by its 32-bit big-endian length. `order` is the
+    /// system's challenge bound, normally `q`.
+    pub fn possession_challenge(
+        order: &BigUint,
+        user: &str,
+        public_values: (&BigUint, &BigUint),
+        commitments: (&BigUint, &BigUint),
+    ) -> BigUint {
+        let mut hasher = Sha256::new();
+        let fields = [
+            public_values.0,
+            public_values.1,
+            commitments.0,
+            commitments.1,
+        ]
+        .map(BigUint::to_bytes_be);
+        for field in [b"register".as_slice(), user.as_bytes()]
+            .into_iter()
+            .chain(fields.iter().map(Vec::as_slice))
+        {
+            hasher.update((field.len() as u32).to_be_bytes());
+            hasher.update(field);
+        }
+        BigUint::from_bytes_be(&hasher.finalize()) % order
+    }
+
+    /// Creates an empty session store with the given settings.
+    fn new_sessions(
+        ttl: Option<Duration>,
+        limit: Option<(usize, EvictionPolicy)>,
+        per_user: PerUserPolicy,
+    ) -> Arc<SessionManager> {
+        let mut sessions = match ttl {
+            Some(ttl) => SessionManager::with_ttl(ttl),
+            None => SessionManager::new(),
+        };
+        if let Some((max, policy)) = limit {
+            sessions = sessions.with_max_sessions(max, policy);
+        }
+        Arc::new(sessions.with_per_user_policy(per_user))
+    }
+
+    fn store_user(&self, user: &str, record: RegistrationRecord) -> Result<(), ZKPError> {
+        let params = self.system.parameters();
+        GroupElement::new(params, record.y1.clone())?;
+        GroupElement::new(params, record.y2.clone())?;
+        let event = AuditEvent::new(user, None, &[&record.y1, &record.y2]);
+        self.users.lock().unwrap().insert(user.to_string(), record);
+        self.metrics.registration();
+        self.auditor.on_register(&event);
+        Ok(())
+    }
+
+    /// Returns the stored record of `user`, if registered.
+    pub fn record(&self, user: &str) -> Option<RegistrationRecord> {
+        self.users.lock().unwrap().get(user).cloned()
+    }
+
+    /// Returns every registered user with their record, sorted by name, for
+    /// backup or migration.
+    pub fn export_users(&self) -> Vec<(String, RegistrationRecord)> {
+        let mut users: Vec<_> = self
+            .users
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(user, record)| (user.clone(), record.clone()))
+            .collect();
+        users.sort_by(|a, b| a.0.cmp(&b.0));
+        users
+    }
+
+    /// Records the commitments (r1, r2) of `user` and issues a challenge.
+    ///
+    /// The challenge differs from every other pending challenge of `user`, so
+    /// a response cannot be replayed across that user's live sessions.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((auth_id, c))` identifying the pending challenge.
+    /// * `Err(ZKPError::UserNotFound)` if `user` is not registered.
+    /// * `Err(ZKPError::SessionCapacityExceeded)` if the session cap is
+    ///   reached and new challenges are refused.
+    /// * `Err(ZKPError::ChallengeAlreadyPending)` if `user` already has a
+    ///   pending challenge and the per-user policy refuses a second.
+    /// * `Err(ZKPError::ChallengeSpaceExhausted)` if no unused challenge was
+    ///   found for `user`; see `SessionManager::create`.
+    /// * `Err(ZKPError)` if either commitment is not a subgroup element.
+    pub fn create_challenge(
+        &self,
+        user: &str,
+        r1: BigUint,
+        r2: BigUint,
+    ) -> Result<(String, BigUint), ZKPError> {
+        let params = self.system.parameters();
+        let r1 = GroupElement::new(params, r1)?.into_inner();
+        let r2 = GroupElement::new(params, r2)?.into_inner();
+
+        let public_values = self.public_values(user).ok_or(ZKPError::UserNotFound)?;
+
+        let (auth_id, challenge) = self.sessions.create(
+            user,
+            (r1.clone(), r2.clone()),
+            public_values.clone(),
+            &*self.generate_challenge,
+        )?;
+        self.metrics.challenge_issued();
+        let (y1, y2) = &public_values;
+        self.auditor.on_challenge(&AuditEvent::new(
+            user,
+            Some(&auth_id),
+            &[y1, y2, &r1, &r2, &challenge],
+        ));
+        Ok((auth_id, challenge))
+    }
+
+    /// Checks the response `s` to the challenge identified by `auth_id`.
+    ///
+    /// The challenge is consumed whatever the outcome, so it cannot be retried.
+    /// The proof is checked against the public values the user had when the
+    /// challenge was issued, so a concurrent re-registration cannot make it
+    /// verify against a key the prover never committed to.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(session_id)` if the proof verifies.
+    /// * `Err(VerifierError::SessionNotFound)` if `auth_id` is unknown, used
+    ///   or expired.
+    /// * `Err(VerifierError::AuthenticationFailed)` if the proof is invalid.
+    pub fn verify(&self, auth_id: &str, s: &BigUint) -> Result<String, VerifierError> {
+        let pending = self
+            .sessions
+            .consume(auth_id)
+            .ok_or(VerifierError::SessionNotFound)?;
+
+        let (r1, r2) = &pending.commitments;
+        let (y1, y2) = &pending.public_values;
+        // the commitments and public values were checked when stored
+        let verifier = Verifier::new(&*self.system).with_subgroup_checks(false);
+        let started = Instant::now();
+        let is_valid = verifier.verify((r1, r2), &pending.challenge, s, (y1, y2));
+        self.metrics.verification(is_valid, started.elapsed());
+        let event = AuditEvent::new(
+            &pending.user,
+            Some(auth_id),
+            &[y1, y2, r1, r2, &pending.challenge, s],
+        );
+        if is_valid {
+            self.auditor.on_verify_success(&event);
+            Ok(ZKPUtils::generate_random_string(32))
+        } else {
+            self.auditor.on_verify_failure(&event);
+            Err(VerifierError::AuthenticationFailed)
+        }
+    }
+
+    /// Returns the number of registered users.
+    pub fn registered_users(&self) -> usize {
+        self.users.lock().unwrap().len()
+    }
+
+    /// Returns the number of challenges issued and not yet answered.
+    pub fn active_sessions(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Drops expired challenges; see `SessionManager::gc`.
+    pub fn gc_sessions(&self) -> usize {
+        self.sessions.gc()
+    }
+}
+
+impl PublicValueStore for AuthService {
+    fn public_values(&self, user: &str) -> Option<(BigUint, BigUint)> {
+        self.users
+            .lock()
+            .unwrap()
+            .get(user)
+            .map(|record| (record.y1.clone(), record.y2.clone()))
+    }
+
+    fn record(&self, user: &str) -> Option<RegistrationRecord> {
+        AuthService::record(self, user)
+    }
+}
@@ -0,0 +1,265 @@
+use crate::error::ZKPError;
+use crate::utils::ZKPUtils;
+use num_bigint::BigUint;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How many challenges `SessionManager::create` draws before giving up on
+/// finding one the user has not got pending.
+const MAX_CHALLENGE_DRAWS: usize = 64;
+
+/// A challenge issued to a user and not yet answered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingChallenge {
+    /// The user the challenge was issued to.
+    pub user: String,
+    /// The challenge `c`.
+    pub challenge: BigUint,
+    /// The commitments (r1, r2) the challenge answers.
+    pub commitments: (BigUint, BigUint),
+    /// The public values (y1, y2) of `user` when the challenge was issued.
+    pub public_values: (BigUint, BigUint),
+}
+
+/// What `SessionManager::create` does when the session cap is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvictionPolicy {
+    /// Refuse the new challenge with `SessionCapacityExceeded`.
+    #[default]
+    RejectNew,
+    /// Drop the oldest pending challenge to make room.
+    EvictOldest,
+}
+
+/// How many challenges one user may have pending at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PerUserPolicy {
+    /// Any number.
+    #[default]
+    Unlimited,
+    /// One; a new challenge invalidates the previous one.
+    ReplacePrevious,
+    /// One; a new challenge is refused with `ChallengeAlreadyPending` until
+    /// the previous one is answered or expires.
+    RefuseNew,
+}
+
+/// The outstanding challenges of an `AuthService`, keyed by auth id.
+///
+/// Every challenge can be consumed once. A challenge never repeats among the
+/// live challenges of the same user, so a response cannot be replayed across
+/// that user's sessions. With a TTL, challenges older than it are treated as
+/// gone and dropped by `gc`. With a cap, at most that many challenges are
+/// held at once, which bounds memory under a burst of challenge requests.
+/// A `PerUserPolicy` can further hold each user to a single live challenge.
+#[derive(Debug, Default)]
+pub struct SessionManager {
+    ttl: Option<Duration>,
+    limit: Option<(usize, EvictionPolicy)>,
+    per_user: PerUserPolicy,
+    sessions: Mutex<Sessions>,
+}
+
+#[derive(Debug, Default)]
+struct Sessions {
+    // auth_id -> (pending challenge, issued at)
+    by_id: HashMap<String, (PendingChallenge, Instant)>,
+    // auth ids in issue order when capped; may still hold ids consumed since
+    order: VecDeque<String>,
+}
+
+impl SessionManager {
+    /// Creates a manager whose challenges never expire.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a manager whose challenges expire `ttl` after being issued.
+    pub fn with_ttl(ttl: Duration) -> Self {
+        Self {
+            ttl: Some(ttl),
+            ..Self::default()
+        }
+    }
+
+    /// Caps the number of challenges held at `max`, applying `policy` when a
+    /// new challenge would exceed it. Expired challenges are dropped first.
+    pub fn with_max_sessions(mut self, max: usize, policy: EvictionPolicy) -> Self {
+        self.limit = Some((max, policy));
+        self
+    }
+
+    /// Limits how many challenges each user may have pending; see
+    /// `PerUserPolicy`.
+    pub fn with_per_user_policy(mut self, policy: PerUserPolicy) -> Self {
+        self.per_user = policy;
+        self
+    }
+
+    /// Returns the configured TTL, if any.
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Returns the configured cap and eviction policy, if any.
+    pub fn max_sessions(&self) -> Option<(usize, EvictionPolicy)> {
+        self.limit
+    }
+
+    /// Returns the configured per-user policy.
+    pub fn per_user_policy(&self) -> PerUserPolicy {
+        self.per_user
+    }
+
+    /// Issues a challenge to `user` for `commitments`.
+    ///
+    /// `public_values` are kept with the challenge, so the response is later
+    /// checked against the key the user had when it was issued even if the
+    /// user re-registers meanwhile.
+    ///
+    /// Challenges are drawn from `generate` until one differs from every live
+    /// challenge of `user`, so `generate` must be able to produce more than
+    /// one value.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((auth_id, c))` identifying the new pending challenge.
+    /// * `Err(ZKPError::SessionCapacityExceeded)` if the cap is reached and
+    ///   the policy is `RejectNew`.
+    /// * `Err(ZKPError::ChallengeAlreadyPending)` if `user` has a live
+    ///   challenge and the per-user policy is `RefuseNew`.
+    /// * `Err(ZKPError::ChallengeSpaceExhausted)` if none of 64 draws from
+    ///   `generate` differs from every live challenge of `user`, e.g. because
+    ///   they already cover a small challenge space.
+    pub fn create(
+        &self,
+        user: &str,
+        commitments: (BigUint, BigUint),
+        public_values: (BigUint, BigUint),
+        mut generate: impl FnMut() -> BigUint,
+    ) -> Result<(String, BigUint), ZKPError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        if self.per_user != PerUserPolicy::Unlimited {
+            let live = sessions
+                .by_id
+                .iter()
+                .find(|(_, (pending, issued_at))| {
+                    pending.user == user && !self.is_expired(*issued_at)
+                })
+                .map(|(auth_id, _)| auth_id.clone());
+            if let Some(auth_id) = live {
+                if self.per_user == PerUserPolicy::RefuseNew {
+                    return Err(ZKPError::ChallengeAlreadyPending);
+                }
+                sessions.by_id.remove(&auth_id);
+            }
+        }
+        if let Some((max, policy)) = self.limit {
+            self.make_room(&mut sessions, max, policy)?;
+        }
+        let challenge = (0..MAX_CHALLENGE_DRAWS)
+            .map(|_| generate())
+            .find(|candidate| {
+                !sessions.by_id.values().any(|(pending, issued_at)| {
+                    !self.is_expired(*issued_at)
+                        && pending.user == user
+                        && pending.challenge == *candidate
+                })
+            })
+            .ok_or(ZKPError::ChallengeSpaceExhausted)?;
+        let auth_id = ZKPUtils::generate_random_string(16);
+        if self.limit.is_some() {
+            sessions.order.push_back(auth_id.clone());
+        }
+        sessions.by_id.insert(
+            auth_id.clone(),
+            (
+                PendingChallenge {
+                    user: user.to_string(),
+                    challenge: challenge.clone(),
+                    commitments,
+                    public_values,
+                },
+                Instant::now(),
+            ),
+        );
+        Ok((auth_id, challenge))
+    }
+
+    /// Removes and returns the challenge identified by `auth_id`.
+    ///
+    /// # Returns
+    ///
+    /// `None` if `auth_id` is unknown, was already consumed, or has expired.
+    pub fn consume(&self, auth_id: &str) -> Option<PendingChallenge> {
+        let (pending, issued_at) = self.sessions.lock().unwrap().by_id.remove(auth_id)?;
+        (!self.is_expired(issued_at)).then_some(pending)
+    }
+
+    /// Drops every expired challenge.
+    ///
+    /// # Returns
+    ///
+    /// The number of challenges dropped.
+    pub fn gc(&self) -> usize {
+        let mut sessions = self.sessions.lock().unwrap();
+        let before = sessions.by_id.len();
+        sessions
+            .by_id
+            .retain(|_, (_, issued_at)| !self.is_expired(*issued_at));
+        let Sessions { by_id, order } = &mut *sessions;
+        order.retain(|auth_id| by_id.contains_key(auth_id));
+        before - by_id.len()
+    }
+
+    /// Returns the number of challenges held, including expired ones not yet
+    /// collected.
+    pub fn len(&self) -> usize {
+        self.sessions.lock().unwrap().by_id.len()
+    }
+
+    /// Returns `true` if no challenges are held.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Frees a slot for a new challenge when `max` are held.
+    ///
+    /// Challenges are issued in order, so expired ones and the oldest live
+    /// one sit at the front of `order`, behind any ids already consumed.
+    fn make_room(
+        &self,
+        sessions: &mut Sessions,
+        max: usize,
+        policy: EvictionPolicy,
+    ) -> Result<(), ZKPError> {
+        let Sessions { by_id, order } = sessions;
+        while let Some(auth_id) = order.front() {
+            match by_id.get(auth_id) {
+                None => {}
+                Some((_, issued_at)) if self.is_expired(*issued_at) => {
+                    by_id.remove(auth_id);
+                }
+                Some(_) if by_id.len() >= max && policy == EvictionPolicy::EvictOldest => {
+                    by_id.remove(auth_id);
+                }
+                Some(_) => break,
+            }
+            order.pop_front();
+        }
+        if by_id.len() >= max {
+            return Err(ZKPError::SessionCapacityExceeded);
+        }
+        // Ids consumed out of order are only skipped at the front; compact
+        // once they outnumber the live ones so `order` stays bounded.
+        if order.len() > 2 * max {
+            order.retain(|auth_id| by_id.contains_key(auth_id));
+        }
+        Ok(())
+    }
+
+    fn is_expired(&self, issued_at: Instant) -> bool {
+        self.ttl.is_some_and(|ttl| issued_at.elapsed() >= ttl)
+    }
+}
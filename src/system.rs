@@ -1,15 +1,66 @@
+use crate::actors::ProofInput;
+use crate::error::ZKPError;
+use crate::group::Group;
+use crate::precompute::FixedBaseTable;
 use crate::protocol::ZKPProtocol;
-use num_bigint::BigUint;
+use crate::utils::ZKPUtils;
+use num_bigint::{BigInt, BigUint};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+/// Validation policy applied by `ZKPSystemBuilder::build`.
+///
+/// Groups the knobs that decide how strictly parameters are checked, so a
+/// deployment can keep them in one (optionally serialized) place and hand
+/// them to the builder with `with_config`. The defaults accept everything
+/// the builder accepted before these knobs existed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct ZKPConfig {
+    /// Reject moduli `p` shorter than this many bits.
+    pub min_modulus_bits: Option<usize>,
+    /// Miller-Rabin rounds for every primality test `build` runs.
+    pub primality_rounds: u32,
+    /// Require `alpha` and `beta` to be members of the order-`q` subgroup
+    /// other than 1.
+    pub subgroup_checks: bool,
+    /// Require `p` and `q` to be prime with `q` dividing `p - 1`.
+    pub validate_parameters: bool,
+}
+
+impl Default for ZKPConfig {
+    fn default() -> Self {
+        Self {
+            min_modulus_bits: None,
+            primality_rounds: 40,
+            subgroup_checks: false,
+            validate_parameters: false,
+        }
+    }
+}
 
 /// Builder for configuring and creating a `ZKPSystem`.
 ///
 /// This struct allows for a flexible way to set up the parameters of the ZKP system.
-/// All parameters (p, q, alpha, beta) must be set before calling `build`.
+/// `p`, `alpha` and `beta` must be set before calling `build`. `q` may be
+/// omitted when `p` is a safe prime, in which case it is derived as `(p - 1) / 2`.
 pub struct ZKPSystemBuilder {
     p: Option<BigUint>,
     q: Option<BigUint>,
     alpha: Option<BigUint>,
     beta: Option<BigUint>,
+    convention: ResponseConvention,
+    precompute: bool,
+    precompute_window: Option<u32>,
+    config: ZKPConfig,
+    parse_error: Option<ZKPError>,
+    c_max: Option<BigUint>,
 }
 
 impl ZKPSystemBuilder {
@@ -20,6 +71,12 @@ impl ZKPSystemBuilder {
             q: None,
             alpha: None,
             beta: None,
+            convention: ResponseConvention::default(),
+            precompute: false,
+            precompute_window: None,
+            config: ZKPConfig::default(),
+            parse_error: None,
+            c_max: None,
         }
     }
 
@@ -47,27 +104,259 @@ impl ZKPSystemBuilder {
         self
     }
 
+    /// Sets `p` from a decimal or `0x`-prefixed hex string.
+    ///
+    /// A malformed string is reported by `build`.
+    pub fn with_prime_str(self, prime: &str) -> Self {
+        self.parse_into(prime, "p", |builder, value| builder.p = Some(value))
+    }
+
+    /// Sets `q` from a decimal or `0x`-prefixed hex string.
+    ///
+    /// A malformed string is reported by `build`.
+    pub fn with_order_str(self, order: &str) -> Self {
+        self.parse_into(order, "q", |builder, value| builder.q = Some(value))
+    }
+
+    /// Sets `alpha` from a decimal or `0x`-prefixed hex string.
+    ///
+    /// A malformed string is reported by `build`.
+    pub fn with_generator_str(self, generator: &str) -> Self {
+        self.parse_into(generator, "alpha", |builder, value| {
+            builder.alpha = Some(value)
+        })
+    }
+
+    /// Sets `beta` from a decimal or `0x`-prefixed hex string.
+    ///
+    /// A malformed string is reported by `build`.
+    pub fn with_second_generator_str(self, generator: &str) -> Self {
+        self.parse_into(generator, "beta", |builder, value| {
+            builder.beta = Some(value)
+        })
+    }
+
+    /// Draws challenges below `c_max` instead of below `q`.
+    ///
+    /// `build` rejects a `c_max` of zero or above `q`.
+    ///
+    /// A short `c_max` also bounds how many challenges one user can have
+    /// pending at once, since an `AuthService` never issues the same
+    /// challenge twice to a user. With the default `PerUserPolicy::Unlimited`
+    /// and no session TTL, a client can fill that space, after which its
+    /// challenge requests fail with `ZKPError::ChallengeSpaceExhausted`. Pair
+    /// a short `c_max` with a per-user policy or a TTL.
+    pub fn with_challenge_modulus(mut self, c_max: BigUint) -> Self {
+        self.c_max = Some(c_max);
+        self
+    }
+
+    /// Sets the sign convention of the response. Defaults to `Subtractive`.
+    pub fn with_response_convention(mut self, convention: ResponseConvention) -> Self {
+        self.convention = convention;
+        self
+    }
+
+    /// Rejects moduli `p` shorter than `bits` bits at build time.
+    ///
+    /// Off by default, so toy groups keep working; production setups can set
+    /// e.g. 2048 to rule out accidentally small groups.
+    pub fn with_min_modulus_bits(mut self, bits: usize) -> Self {
+        self.config.min_modulus_bits = Some(bits);
+        self
+    }
+
+    /// Replaces the validation policy, including any minimum modulus size
+    /// set earlier with `with_min_modulus_bits`.
+    pub fn with_config(mut self, config: ZKPConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Precomputes fixed-base tables for `alpha` and `beta` at build time.
+    ///
+    /// Speeds up commitments, public values and verification at the cost of
+    /// memory. The window width follows the size of `p`; see
+    /// `FixedBaseTable::window_for_modulus_bits`.
+    pub fn with_precomputation(mut self) -> Self {
+        self.precompute = true;
+        self
+    }
+
+    /// Precomputes fixed-base tables with an explicit window width.
+    ///
+    /// Overrides the width `with_precomputation` would pick from `p`.
+    pub fn with_precompute_window(mut self, window: u32) -> Self {
+        self.precompute = true;
+        self.precompute_window = Some(window);
+        self
+    }
+
     /// Builds the `ZKPSystem` with the configured parameters.
     ///
     /// # Returns
     ///
     /// * `Ok(ZKPSystem)` if all required parameters are set.
-    /// * `Err(&'static str)` if any parameter is missing.
-    pub fn build(self) -> Result<ZKPSystem, &'static str> {
-        let p = self.p.ok_or("Prime p is required")?;
-        let q = self.q.ok_or("Order q is required")?;
-        let alpha = self.alpha.ok_or("Generator alpha is required")?;
-        let beta = self.beta.ok_or("Second generator beta is required")?;
+    /// * `Err(ZKPError::InvalidNumber)` if a string given to one of the `_str`
+    ///   setters did not parse; this is reported first.
+    /// * `Err(ZKPError::MissingParameter)` if a parameter is missing, or if
+    ///   `q` is omitted and `p` is not a safe prime.
+    /// * `Err(ZKPError::ModulusTooSmall)` if `p` is below the minimum size.
+    /// * `Err(ZKPError::InvalidChallengeModulus)` if `c_max` is outside
+    ///   `1..=q`.
+    /// * `Err(ZKPError::GeneratorsNotDistinct)` if `alpha == beta`, since the
+    ///   two equations would then coincide.
+    /// * `Err(ZKPError::InvalidParameters)` or
+    ///   `Err(ZKPError::GeneratorNotInSubgroup)` if a check enabled in the
+    ///   `ZKPConfig` fails.
+    /// * `Err(ZKPError::InvalidPrecomputeWindow)` if the precompute window is
+    ///   not between 1 and 16.
+    pub fn build(self) -> Result<ZKPSystem, ZKPError> {
+        if let Some(error) = self.parse_error {
+            return Err(error);
+        }
+        let config = &self.config;
+        let p = self.p.ok_or(ZKPError::MissingParameter("p"))?;
+        if config
+            .min_modulus_bits
+            .is_some_and(|min| p.bits() < min as u64)
+        {
+            return Err(ZKPError::ModulusTooSmall);
+        }
+        let q = match self.q {
+            Some(q) => q,
+            None => Self::derive_safe_prime_order(&p, config.primality_rounds)
+                .ok_or(ZKPError::MissingParameter("q"))?,
+        };
+        if self
+            .c_max
+            .as_ref()
+            .is_some_and(|c_max| *c_max == BigUint::from(0u32) || *c_max > q)
+        {
+            return Err(ZKPError::InvalidChallengeModulus);
+        }
+        let alpha = self.alpha.ok_or(ZKPError::MissingParameter("alpha"))?;
+        let beta = self.beta.ok_or(ZKPError::MissingParameter("beta"))?;
+        if alpha == beta {
+            return Err(ZKPError::GeneratorsNotDistinct);
+        }
+        if config.validate_parameters && !Self::is_valid_group(&p, &q, config.primality_rounds) {
+            return Err(ZKPError::InvalidParameters);
+        }
+        let one = BigUint::from(1u32);
+        let has_order_q = |g: &BigUint| *g != one && g.modpow(&q, &p) == one;
+        if config.subgroup_checks && !(has_order_q(&alpha) && has_order_q(&beta)) {
+            return Err(ZKPError::GeneratorNotInSubgroup);
+        }
+
+        let tables = if self.precompute {
+            let window = self
+                .precompute_window
+                .unwrap_or_else(|| FixedBaseTable::window_for_modulus_bits(p.bits()));
+            if !(1..=FixedBaseTable::MAX_WINDOW).contains(&window) {
+                return Err(ZKPError::InvalidPrecomputeWindow);
+            }
+            Some(Arc::new(GeneratorTables {
+                alpha: FixedBaseTable::with_window(&alpha, &p, q.bits(), window),
+                beta: FixedBaseTable::with_window(&beta, &p, q.bits(), window),
+            }))
+        } else {
+            None
+        };
+
+        Ok(ZKPSystem {
+            parameters: ZKPParameters {
+                p,
+                q,
+                alpha,
+                beta,
+                convention: self.convention,
+                c_max: self.c_max,
+            },
+            tables,
+        })
+    }
+}
+
+impl ZKPSystemBuilder {
+    /// Parses `text` and stores it with `set`, or records
+    /// `ZKPError::InvalidNumber(name)` for `build` if it is malformed. The
+    /// first error recorded wins.
+    fn parse_into(
+        mut self,
+        text: &str,
+        name: &'static str,
+        set: impl FnOnce(&mut Self, BigUint),
+    ) -> Self {
+        match Self::parse_number(text) {
+            Some(value) => set(&mut self, value),
+            None => {
+                self.parse_error
+                    .get_or_insert(ZKPError::InvalidNumber(name));
+            }
+        }
+        self
+    }
+
+    /// Parses a decimal or `0x`-prefixed hex number with no sign, spaces or
+    /// separators.
+    fn parse_number(text: &str) -> Option<BigUint> {
+        let (digits, radix) = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+            Some(hex) => (hex, 16),
+            None => (text, 10),
+        };
+        if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+            return None;
+        }
+        BigUint::parse_bytes(digits.as_bytes(), radix)
+    }
+
+    /// Returns `q = (p - 1) / 2` if both `p` and `q` are prime.
+    fn derive_safe_prime_order(p: &BigUint, rounds: u32) -> Option<BigUint> {
+        if *p < BigUint::from(5u32) {
+            return None;
+        }
+        let q = (p - 1u32) >> 1;
+        let is_safe_prime =
+            ZKPUtils::is_probable_prime(&q, rounds) && ZKPUtils::is_probable_prime(p, rounds);
+        is_safe_prime.then_some(q)
+    }
+
+    /// Returns `true` if `p` and `q` are prime and `q` divides `p - 1`.
+    fn is_valid_group(p: &BigUint, q: &BigUint, rounds: u32) -> bool {
+        ZKPUtils::is_probable_prime(q, rounds)
+            && ZKPUtils::is_probable_prime(p, rounds)
+            && ((p - 1u32) % q) == BigUint::from(0u32)
+    }
+}
 
-        Ok(ZKPSystem::new(p, q, alpha, beta))
+impl Default for ZKPSystemBuilder {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
+/// Fixed-base tables for the two generators.
+struct GeneratorTables {
+    alpha: FixedBaseTable,
+    beta: FixedBaseTable,
+}
+
 /// Represents the Chaum-Pedersen Zero-Knowledge Proof System.
 ///
 /// This struct holds the system parameters and implements the `ZKPProtocol` trait.
+///
+/// Provers and verifiers borrow the system, so a system built once with
+/// precomputed tables can be put behind an `Arc` and used by any number of
+/// verifiers on any number of threads without rebuilding the tables.
+///
+/// The tables are never modified after `build`, so clones share them through
+/// an `Arc`: cloning copies the parameters and bumps a reference count, and
+/// never rebuilds or deep-copies the tables.
+#[derive(Clone)]
 pub struct ZKPSystem {
     parameters: ZKPParameters,
+    tables: Option<Arc<GeneratorTables>>,
 }
 
 impl ZKPSystem {
@@ -81,7 +370,15 @@ impl ZKPSystem {
     /// * `beta` - The second generator.
     pub fn new(p: BigUint, q: BigUint, alpha: BigUint, beta: BigUint) -> Self {
         Self {
-            parameters: ZKPParameters { p, q, alpha, beta },
+            parameters: ZKPParameters {
+                p,
+                q,
+                alpha,
+                beta,
+                convention: ResponseConvention::default(),
+                c_max: None,
+            },
+            tables: None,
         }
     }
 
@@ -90,15 +387,170 @@ impl ZKPSystem {
         ZKPSystemBuilder::new()
     }
 
+    /// Returns a builder pre-populated with `params`, including the response
+    /// convention and challenge modulus, so that one field can be changed before rebuilding.
+    ///
+    /// Precomputation and the minimum modulus size are not part of the
+    /// parameters and start unset.
+    pub fn builder_from(params: &ZKPParameters) -> ZKPSystemBuilder {
+        let builder = ZKPSystemBuilder::new()
+            .with_prime(params.p.clone())
+            .with_order(params.q.clone())
+            .with_generator(params.alpha.clone())
+            .with_second_generator(params.beta.clone())
+            .with_response_convention(params.convention);
+        match &params.c_max {
+            Some(c_max) => builder.with_challenge_modulus(c_max.clone()),
+            None => builder,
+        }
+    }
+
     /// Returns a reference to the system parameters.
     pub fn parameters(&self) -> &ZKPParameters {
         &self.parameters
     }
+
+    /// Consumes the system and returns its parameters by value.
+    ///
+    /// Any precomputed tables are dropped.
+    pub fn parameters_owned(self) -> ZKPParameters {
+        self.parameters
+    }
+
+    /// Returns the window width of the fixed-base tables, if precomputed.
+    pub fn precompute_window(&self) -> Option<u32> {
+        self.tables.as_ref().map(|tables| tables.alpha.window())
+    }
+
+    /// Runs the challenge and verification steps in process.
+    ///
+    /// Generates a random challenge, obtains the prover's response from
+    /// `respond`, and verifies it against the given commitments.
+    ///
+    /// # Arguments
+    ///
+    /// * `commitments` - The commitments (r1, r2) already sent by the prover.
+    /// * `public_values` - The public values (y1, y2) of the prover.
+    /// * `respond` - Computes the response `s` for a challenge `c`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the proof is valid, `false` otherwise.
+    pub fn challenge_then_verify(
+        &self,
+        commitments: (&BigUint, &BigUint),
+        public_values: (&BigUint, &BigUint),
+        respond: impl FnOnce(&BigUint) -> BigUint,
+    ) -> bool {
+        let challenge = ZKPUtils::generate_random_below(self.parameters.challenge_bound());
+        let response = respond(&challenge);
+        self.verify(commitments, &challenge, &response, public_values)
+    }
+
+    /// Runs one full interactive proof for `secret` in process.
+    ///
+    /// Commits with a fresh nonce, draws a challenge, responds and verifies
+    /// against the public values derived from `secret`. Meant for examples
+    /// and sanity checks where plumbing a challenge through is not worth it.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the proof is valid, `false` otherwise.
+    pub fn prove_and_verify(&self, secret: &BigUint) -> bool {
+        let public_values = self.compute_public_values(secret);
+        self.prove_and_verify_against(secret, (&public_values.0, &public_values.1))
+    }
+
+    /// Like `prove_and_verify`, but verifies against the given public values
+    /// (y1, y2) instead of those derived from `secret`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the proof is valid, `false` otherwise.
+    pub fn prove_and_verify_against(
+        &self,
+        secret: &BigUint,
+        public_values: (&BigUint, &BigUint),
+    ) -> bool {
+        let nonce = ZKPUtils::generate_random_below(&self.parameters.q);
+        let (r1, r2) = self.compute_commitments(&nonce);
+        self.challenge_then_verify((&r1, &r2), public_values, |challenge| {
+            self.compute_response(&nonce, challenge, secret)
+        })
+    }
+
+    /// Checks a proof of possession sent with a registration.
+    ///
+    /// Accepts only if `y1`, `y2` and both commitments are elements of the
+    /// order-`q` subgroup and the proof shows knowledge of the discrete log of
+    /// `(y1, y2)`. `challenge` is the one the registration flow derived, e.g.
+    /// with `AuthService::possession_challenge`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the public values may be stored, `false` otherwise.
+    pub fn verify_registration_proof(
+        &self,
+        y1: &BigUint,
+        y2: &BigUint,
+        commitments: (&BigUint, &BigUint),
+        challenge: &BigUint,
+        response: &BigUint,
+    ) -> bool {
+        [y1, y2, commitments.0, commitments.1]
+            .into_iter()
+            .all(|value| self.parameters.subgroup_contains(value))
+            && self.verify(commitments, challenge, response, (y1, y2))
+    }
+
+    /// Runs a known-answer prove/verify cycle with a fixed secret, nonce and
+    /// challenge.
+    ///
+    /// Checks that the (possibly precomputed) exponentiations agree with plain
+    /// `modpow`, that the honest proof verifies and that a proof with a
+    /// tampered response does not. Meant to be called once at startup to
+    /// catch a broken build or bad parameters before serving.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if every check passed.
+    /// * `Err(ZKPError::SelfTestFailed)` naming the first check that failed.
+    pub fn self_test(&self) -> Result<(), ZKPError> {
+        let params = &self.parameters;
+        let q = &params.q;
+        let secret = BigUint::from(0x0123_4567_89ab_cdefu64) % q;
+        let nonce = BigUint::from(0xfedc_ba98_7654_3210u64) % q;
+        let challenge = BigUint::from(0x1f2e_3d4c_5b6a_7988u64) % q;
+
+        let public_values = self.compute_public_values(&secret);
+        if public_values != params.compute_public_keys(&secret) {
+            return Err(ZKPError::SelfTestFailed("public values"));
+        }
+        let commitments = self.compute_commitments(&nonce);
+        if commitments != params.compute_commitments(&nonce) {
+            return Err(ZKPError::SelfTestFailed("commitments"));
+        }
+
+        let (y1, y2) = &public_values;
+        let (r1, r2) = &commitments;
+        let response = self.compute_response(&nonce, &challenge, &secret);
+        if !self.verify((r1, r2), &challenge, &response, (y1, y2)) {
+            return Err(ZKPError::SelfTestFailed("honest proof rejected"));
+        }
+        let tampered = (&response + 1u32) % q;
+        if self.verify((r1, r2), &challenge, &tampered, (y1, y2)) {
+            return Err(ZKPError::SelfTestFailed("tampered proof accepted"));
+        }
+        Ok(())
+    }
 }
 
 impl ZKPProtocol for ZKPSystem {
     fn compute_commitments(&self, randomness: &BigUint) -> (BigUint, BigUint) {
-        self.parameters.compute_commitments(randomness)
+        match &self.tables {
+            Some(tables) => (tables.alpha.pow(randomness), tables.beta.pow(randomness)),
+            None => self.parameters.compute_commitments(randomness),
+        }
     }
 
     fn compute_response(
@@ -118,21 +570,111 @@ impl ZKPProtocol for ZKPSystem {
         response: &BigUint,
         public_keys: (&BigUint, &BigUint),
     ) -> bool {
-        self.parameters
-            .verify(commitments, challenge, response, public_keys)
+        let (cond1, cond2) = self.verify_equations(commitments, challenge, response, public_keys);
+        cond1 && cond2
+    }
+
+    fn verify_equations(
+        &self,
+        commitments: (&BigUint, &BigUint),
+        challenge: &BigUint,
+        response: &BigUint,
+        public_keys: (&BigUint, &BigUint),
+    ) -> (bool, bool) {
+        let Some(tables) = &self.tables else {
+            return self
+                .parameters
+                .verify_equations(commitments, challenge, response, public_keys);
+        };
+        let (r1, r2) = commitments;
+        let (y1, y2) = public_keys;
+        if !self.parameters.accepts_challenge(challenge)
+            || !self.parameters.fits_modulus_width([r1, r2, y1, y2])
+        {
+            return (false, false);
+        }
+        let p = &self.parameters.p;
+        let exponent = self.parameters.verification_exponent(challenge);
+
+        (
+            *r1 == tables.alpha.pow(response) * y1.modpow(&exponent, p) % p,
+            *r2 == tables.beta.pow(response) * y2.modpow(&exponent, p) % p,
+        )
+    }
+
+    /// Computes each distinct `y^c` only once across the batch, which saves
+    /// `modpow`s when the same public value meets the same challenge more than
+    /// once.
+    fn verify_batch(&self, proofs: &[ProofInput]) -> Vec<bool> {
+        let p = &self.parameters.p;
+        let mut powers: HashMap<(BigUint, BigUint), BigUint> = HashMap::new();
+        let mut pow = |y: &BigUint, exponent: &BigUint| -> BigUint {
+            powers
+                .entry((y.clone(), exponent.clone()))
+                .or_insert_with(|| y.modpow(exponent, p))
+                .clone()
+        };
+
+        proofs
+            .iter()
+            .map(|proof| {
+                let (r1, r2) = &proof.commitments;
+                let (y1, y2) = &proof.public_values;
+                if !self.parameters.accepts_challenge(&proof.challenge)
+                    || !self.parameters.fits_modulus_width([r1, r2, y1, y2])
+                {
+                    return false;
+                }
+                let exponent = self.parameters.verification_exponent(&proof.challenge);
+                // (alpha^s, beta^s), through the tables when precomputed.
+                let (alpha_s, beta_s) = self.compute_commitments(&proof.response);
+
+                *r1 == alpha_s * pow(y1, &exponent) % p && *r2 == beta_s * pow(y2, &exponent) % p
+            })
+            .collect()
     }
 
     fn compute_public_values(&self, secret: &BigUint) -> (BigUint, BigUint) {
-        self.parameters.compute_public_keys(secret)
+        match &self.tables {
+            Some(tables) => (tables.alpha.pow(secret), tables.beta.pow(secret)),
+            None => self.parameters.compute_public_keys(secret),
+        }
     }
 
     fn get_order(&self) -> &BigUint {
         &self.parameters.q
     }
+
+    fn hash_domain(&self) -> Vec<u8> {
+        self.parameters.hash_domain()
+    }
+
+    fn is_group_element(&self, value: &BigUint) -> bool {
+        self.parameters.subgroup_contains(value)
+    }
+
+    fn challenge_bound(&self) -> &BigUint {
+        self.parameters.challenge_bound()
+    }
+}
+
+/// The sign convention used for the response `s`.
+///
+/// Implementations differ here; both are sound, but a transcript produced
+/// under one convention does not verify under the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResponseConvention {
+    /// `s = k - c * x mod q`, verified as `r = alpha^s * y^c mod p`.
+    #[default]
+    Subtractive,
+    /// `s = k + c * x mod q`, verified as `r = alpha^s * y^-c mod p`.
+    ///
+    /// Matches common Python teaching implementations.
+    Additive,
 }
 
 /// Holds the immutable parameters of the ZKP system.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ZKPParameters {
     /// The prime modulus.
     pub p: BigUint,
@@ -142,17 +684,255 @@ pub struct ZKPParameters {
     pub alpha: BigUint,
     /// The second generator.
     pub beta: BigUint,
+    /// The sign convention of the response.
+    pub convention: ResponseConvention,
+    /// Exclusive bound on challenges, for short-challenge parameter sets;
+    /// `None` means `q`. Must not exceed `q`.
+    pub c_max: Option<BigUint>,
+}
+
+/// Converts the `(alpha, beta, p, q)` tuple returned by
+/// `ZKPUtils::get_1024_bit_constants` and `get_2048_bit_constants`.
+///
+/// Note the tuple order differs from the field order `(p, q, alpha, beta)`;
+/// this conversion maps each element to the right field so callers do not
+/// have to. The response convention is the default.
+impl From<(BigUint, BigUint, BigUint, BigUint)> for ZKPParameters {
+    fn from((alpha, beta, p, q): (BigUint, BigUint, BigUint, BigUint)) -> Self {
+        Self {
+            p,
+            q,
+            alpha,
+            beta,
+            convention: ResponseConvention::default(),
+            c_max: None,
+        }
+    }
 }
 
 impl ZKPParameters {
+    /// Builds parameters from signed integers, as produced by some libraries.
+    ///
+    /// Uses the default `ResponseConvention`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ZKPParameters)` if all values are non-negative.
+    /// * `Err(ZKPError::NegativeParameter)` naming the first negative value.
+    pub fn from_bigint(
+        p: BigInt,
+        q: BigInt,
+        alpha: BigInt,
+        beta: BigInt,
+    ) -> Result<ZKPParameters, ZKPError> {
+        let unsigned = |v: BigInt, name| v.to_biguint().ok_or(ZKPError::NegativeParameter(name));
+        Ok(ZKPParameters {
+            p: unsigned(p, "p")?,
+            q: unsigned(q, "q")?,
+            alpha: unsigned(alpha, "alpha")?,
+            beta: unsigned(beta, "beta")?,
+            convention: ResponseConvention::default(),
+            c_max: None,
+        })
+    }
+
+    /// The serialization format version written by `to_writer`.
+    pub const ENCODING_VERSION: u8 = 1;
+
+    /// Writes the parameters to `writer`.
+    ///
+    /// The encoding is a version byte, then `p`, `q`, `alpha` and `beta`, each
+    /// as a 4-byte big-endian length and the big-endian bytes of the value,
+    /// then the convention (0 subtractive, 1 additive), then 0 for no
+    /// `c_max` or 1 followed by `c_max` encoded like the other values.
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut out = vec![Self::ENCODING_VERSION];
+        for value in [&self.p, &self.q, &self.alpha, &self.beta] {
+            ZKPUtils::write_length_prefixed(&mut out, value);
+        }
+        out.push(match self.convention {
+            ResponseConvention::Subtractive => 0,
+            ResponseConvention::Additive => 1,
+        });
+        match &self.c_max {
+            Some(c_max) => {
+                out.push(1);
+                ZKPUtils::write_length_prefixed(&mut out, c_max);
+            }
+            None => out.push(0),
+        }
+        writer.write_all(&out)
+    }
+
+    /// Reads parameters written by `to_writer`, consuming exactly their
+    /// encoding from `reader`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ZKPParameters)` if the encoding is well-formed.
+    /// * `Err` of kind `UnexpectedEof` if the input ends early.
+    /// * `Err` of kind `InvalidData` wrapping
+    ///   `ZKPError::UnsupportedParametersVersion` for an unknown version byte,
+    ///   or `ZKPError::MalformedParameters` for an unknown convention or
+    ///   `c_max` tag.
+    /// * `Err` with any other error of `reader`.
+    pub fn from_reader<R: Read>(mut reader: R) -> io::Result<Self> {
+        let invalid = |e: ZKPError| io::Error::new(io::ErrorKind::InvalidData, e);
+        let byte = |reader: &mut R| -> io::Result<u8> {
+            let mut byte = [0u8];
+            reader.read_exact(&mut byte)?;
+            Ok(byte[0])
+        };
+
+        let version = byte(&mut reader)?;
+        if version != Self::ENCODING_VERSION {
+            return Err(invalid(ZKPError::UnsupportedParametersVersion(version)));
+        }
+        let p = ZKPUtils::read_length_prefixed(&mut reader)?;
+        let q = ZKPUtils::read_length_prefixed(&mut reader)?;
+        let alpha = ZKPUtils::read_length_prefixed(&mut reader)?;
+        let beta = ZKPUtils::read_length_prefixed(&mut reader)?;
+        let convention = match byte(&mut reader)? {
+            0 => ResponseConvention::Subtractive,
+            1 => ResponseConvention::Additive,
+            _ => return Err(invalid(ZKPError::MalformedParameters)),
+        };
+        let c_max = match byte(&mut reader)? {
+            0 => None,
+            1 => Some(ZKPUtils::read_length_prefixed(&mut reader)?),
+            _ => return Err(invalid(ZKPError::MalformedParameters)),
+        };
+        Ok(Self {
+            p,
+            q,
+            alpha,
+            beta,
+            convention,
+            c_max,
+        })
+    }
+
+    /// Returns a canonical identifier of the group, for binding proofs to it.
+    ///
+    /// `SHA-256(p || q || alpha || beta)`, each value left-padded with zeros to
+    /// the byte length of `p` so the encoding is unambiguous. Folded into
+    /// Fiat-Shamir challenges, it keeps a proof made under one group from
+    /// verifying under another.
+    pub fn hash_domain(&self) -> Vec<u8> {
+        let width = self.p.bits().div_ceil(8) as usize;
+        let mut hasher = Sha256::new();
+        for value in [&self.p, &self.q, &self.alpha, &self.beta] {
+            let bytes = value.to_bytes_be();
+            let padding = width.saturating_sub(bytes.len());
+            hasher.update(vec![0u8; padding]);
+            hasher.update(&bytes);
+        }
+        hasher.finalize().to_vec()
+    }
+
+    /// Returns the cofactor `h = (p - 1) / q`.
+    ///
+    /// `h == 2` for a safe prime; a larger `h` means Z_p* has other small
+    /// subgroups, which matters when elements are not subgroup-checked.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(h)` if `q` divides `p - 1`.
+    /// * `Err(ZKPError::OrderDoesNotDivide)` otherwise.
+    pub fn cofactor(&self) -> Result<BigUint, ZKPError> {
+        let p_minus_one = &self.p - 1u32;
+        if self.q == BigUint::from(0u32) || &p_minus_one % &self.q != BigUint::from(0u32) {
+            return Err(ZKPError::OrderDoesNotDivide);
+        }
+        Ok(p_minus_one / &self.q)
+    }
+
+    /// Returns the group the parameters live in, borrowing `p` and `q`.
+    pub fn group(&self) -> Group<'_> {
+        Group::borrowed(&self.p, &self.q)
+    }
+
+    /// Reduces `v` modulo `p`.
+    pub fn reduce_p(&self, v: &BigUint) -> BigUint {
+        self.group().reduce(v)
+    }
+
+    /// Reduces `v` modulo `q`.
+    pub fn reduce_q(&self, v: &BigUint) -> BigUint {
+        self.group().reduce_exponent(v)
+    }
+
+    /// Checks whether `v` is an element of the order-`q` subgroup of Z_p*.
+    ///
+    /// 1 <= v < p and v^q mod p == 1
+    pub fn subgroup_contains(&self, v: &BigUint) -> bool {
+        self.group().subgroup_contains(v)
+    }
+
+    /// Returns a random element of the order-`q` subgroup.
+    ///
+    /// alpha^r mod p for a random `r < q`
+    ///
+    /// Handy as a valid but unrelated group element, e.g. another user's
+    /// public value in negative tests. Nobody knows its discrete logarithm
+    /// once `r` is dropped.
+    pub fn random_subgroup_element(&self) -> BigUint {
+        let r = ZKPUtils::generate_random_below(&self.q);
+        self.group().pow(&self.alpha, &r)
+    }
+
+    /// Derives a replacement second generator from `seed`.
+    ///
+    /// h = SHA-256(seed || counter) mod q
+    /// beta = alpha^h mod p
+    ///
+    /// The counter starts at 0 and is bumped until `h != 0`, so the result is
+    /// never 1. Being a non-trivial power of `alpha`, it generates the same
+    /// order-`q` subgroup.
+    ///
+    /// # Security
+    ///
+    /// Anyone who knows `seed` knows `log_alpha(beta) = h`. The proof still
+    /// shows knowledge of `x` for `y1`, but `y2 = y1^h` is then implied, so the
+    /// second equation adds nothing and the group no longer provides two
+    /// generators with an unknown relation. Only use a derived beta where that
+    /// independence is not relied upon, or keep `seed` secret and discard it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` is 1 or outside the subgroup, since no valid beta can
+    /// be derived from it.
+    pub fn derive_new_beta(&self, seed: &[u8]) -> BigUint {
+        let one = BigUint::from(1u32);
+        assert!(
+            self.alpha != one && self.subgroup_contains(&self.alpha),
+            "alpha must generate the order-q subgroup"
+        );
+        let mut counter = 0u32;
+        loop {
+            let digest = Sha256::new()
+                .chain_update(seed)
+                .chain_update(counter.to_be_bytes())
+                .finalize();
+            let h = BigUint::from_bytes_be(&digest) % &self.q;
+            let beta = self.alpha.modpow(&h, &self.p);
+            if beta != one && self.subgroup_contains(&beta) {
+                return beta;
+            }
+            counter += 1;
+        }
+    }
+
     /// Computes the public keys corresponding to a secret.
     ///
     /// y1 = alpha^x mod p
     /// y2 = beta^x mod p
     pub fn compute_public_keys(&self, secret: &BigUint) -> (BigUint, BigUint) {
-        let y1 = self.alpha.modpow(secret, &self.p);
-        let y2 = self.beta.modpow(secret, &self.p);
-        (y1, y2)
+        let group = self.group();
+        (
+            group.pow(&self.alpha, secret),
+            group.pow(&self.beta, secret),
+        )
     }
 
     /// Computes the commitments for the proof.
@@ -160,25 +940,70 @@ impl ZKPParameters {
     /// r1 = alpha^k mod p
     /// r2 = beta^k mod p
     pub fn compute_commitments(&self, randomness: &BigUint) -> (BigUint, BigUint) {
-        let r1 = self.alpha.modpow(randomness, &self.p);
-        let r2 = self.beta.modpow(randomness, &self.p);
-        (r1, r2)
+        let group = self.group();
+        (
+            group.pow(&self.alpha, randomness),
+            group.pow(&self.beta, randomness),
+        )
     }
 
     /// Computes the response to the challenge.
     ///
-    /// s = k - c * x mod q
+    /// s = k - c * x mod q (`Subtractive`)
+    /// s = k + c * x mod q (`Additive`)
     pub fn compute_response(
         &self,
         randomness: &BigUint,
         challenge: &BigUint,
         secret: &BigUint,
     ) -> BigUint {
-        if *randomness >= challenge * secret {
-            (randomness - challenge * secret).modpow(&BigUint::from(1u32), &self.q)
-        } else {
-            &self.q - (challenge * secret - randomness).modpow(&BigUint::from(1u32), &self.q)
+        if self.convention == ResponseConvention::Additive {
+            return self.reduce_q(&(randomness + challenge * secret));
+        }
+        // Computed as `(k mod q) + q - (c * x mod q)`, which is never negative,
+        // then reduced once. Branching on `k >= c * x` instead would make the
+        // two cases cost differently and leak that comparison, which depends
+        // on the secret, through timing. `BigUint` arithmetic itself is not
+        // constant-time, so this removes the branch, not every timing signal.
+        let cx = self.reduce_q(&(challenge * secret));
+        self.reduce_q(&(self.reduce_q(randomness) + &self.q - cx))
+    }
+
+    /// Recovers the secret from two transcripts that reused the same commitment.
+    ///
+    /// Given `(c1, s1)` and `(c2, s2)` answered with the same randomness `k`,
+    /// `s1 - s2 = (c2 - c1) * x mod q` under the subtractive convention, so
+    /// x = (s2 - s1) * (c1 - c2)^-1 mod q
+    /// and `x = (s1 - s2) * (c1 - c2)^-1 mod q` under the additive one.
+    ///
+    /// This is a diagnostic showing why nonce reuse leaks the secret.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(x)` if the challenges differ modulo `q`.
+    /// * `None` if they are equal, in which case nothing can be recovered.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn recover_secret_from_transcripts(
+        &self,
+        first: (&BigUint, &BigUint),
+        second: (&BigUint, &BigUint),
+    ) -> Option<BigUint> {
+        let (c1, s1) = first;
+        let (c2, s2) = second;
+        let q = &self.q;
+
+        let c_diff = (c1 % q + q - c2 % q) % q;
+        if c_diff == BigUint::from(0u32) {
+            return None;
         }
+        let s_diff = match self.convention {
+            ResponseConvention::Subtractive => (s2 % q + q - s1 % q) % q,
+            ResponseConvention::Additive => (s1 % q + q - s2 % q) % q,
+        };
+
+        // q is prime, so c_diff^(q-2) is its inverse.
+        let c_diff_inv = c_diff.modpow(&(q - 2u32), q);
+        Some(s_diff * c_diff_inv % q)
     }
 
     /// Verifies the proof.
@@ -186,6 +1011,19 @@ impl ZKPParameters {
     /// Checks if:
     /// r1 == alpha^s * y1^c mod p
     /// r2 == beta^s * y2^c mod p
+    ///
+    /// Under the `Additive` convention `y^c` is replaced with `y^-c`, computed
+    /// as `y^(q - c mod q)` since `y` has order `q`.
+    ///
+    /// Each side is computed as one simultaneous exponentiation with
+    /// `ZKPUtils::modpow_multi`. Commitments or public values that take more
+    /// bytes than `p` are rejected before any exponentiation.
+    ///
+    /// # Challenge space
+    ///
+    /// A challenge at or above `c_max` is rejected. Any other challenge is
+    /// reduced modulo `q` before it is used as an exponent, so `c` and
+    /// `c + q` always give the same result, with or without `c_max`.
     pub fn verify(
         &self,
         commitments: (&BigUint, &BigUint),
@@ -193,17 +1031,121 @@ impl ZKPParameters {
         response: &BigUint,
         public_keys: (&BigUint, &BigUint),
     ) -> bool {
+        let (cond1, cond2) = self.verify_equations(commitments, challenge, response, public_keys);
+        cond1 && cond2
+    }
+
+    /// Checks the two verification equations separately.
+    ///
+    /// # Returns
+    ///
+    /// `(first, second)`: whether `r1 == alpha^s * y1^c` and
+    /// `r2 == beta^s * y2^c` (with `c` negated under the additive convention).
+    pub fn verify_equations(
+        &self,
+        commitments: (&BigUint, &BigUint),
+        challenge: &BigUint,
+        response: &BigUint,
+        public_keys: (&BigUint, &BigUint),
+    ) -> (bool, bool) {
         let (r1, r2) = commitments;
         let (y1, y2) = public_keys;
+        if !self.accepts_challenge(challenge) || !self.fits_modulus_width([r1, r2, y1, y2]) {
+            return (false, false);
+        }
+        let exponent = self.verification_exponent(challenge);
 
-        let cond1 = *r1
-            == (&self.alpha.modpow(response, &self.p) * y1.modpow(challenge, &self.p))
-                .modpow(&BigUint::from(1u32), &self.p);
+        let cond1 =
+            *r1 == ZKPUtils::modpow_multi(&[(&self.alpha, response), (y1, &exponent)], &self.p);
 
-        let cond2 = *r2
-            == (&self.beta.modpow(response, &self.p) * y2.modpow(challenge, &self.p))
-                .modpow(&BigUint::from(1u32), &self.p);
+        let cond2 =
+            *r2 == ZKPUtils::modpow_multi(&[(&self.beta, response), (y2, &exponent)], &self.p);
 
-        cond1 && cond2
+        (cond1, cond2)
+    }
+
+    /// Checks a whole batch with one random linear combination.
+    ///
+    /// With random 128-bit weights `w_i`, checks
+    ///
+    /// prod r1_i^w_i == alpha^(sum w_i * s_i) * prod y1_i^(w_i * e_i) mod p
+    ///
+    /// and the same for `r2`, `beta` and `y2`, where `e_i` is the exponent
+    /// `verify` applies to `c_i`. The generators are raised once for the
+    /// whole batch instead of once per proof. A `false` result only says that
+    /// some proof is invalid; check them one by one to find which.
+    ///
+    /// # Security
+    ///
+    /// Every `r` and `y` must already be known to be a subgroup element
+    /// (e.g. through `GroupElement`). A value that is off by a small-order
+    /// factor can cancel out in the combination and let an invalid proof pass
+    /// with non-negligible probability, which is why `ZKPSystem::verify_batch`
+    /// does not use this.
+    pub fn verify_batch_combined(&self, proofs: &[ProofInput]) -> bool {
+        let well_formed = proofs.iter().all(|proof| {
+            let (r1, r2) = &proof.commitments;
+            let (y1, y2) = &proof.public_values;
+            self.accepts_challenge(&proof.challenge) && self.fits_modulus_width([r1, r2, y1, y2])
+        });
+        if !well_formed {
+            return false;
+        }
+        let p = &self.p;
+        let weight_bound = BigUint::from(1u32) << 128;
+        let mut response_sum = BigUint::from(0u32);
+        let mut lhs = (BigUint::from(1u32), BigUint::from(1u32));
+        let mut rhs = (BigUint::from(1u32), BigUint::from(1u32));
+        for proof in proofs {
+            let weight = ZKPUtils::generate_random_below(&weight_bound);
+            let exponent = &weight * self.verification_exponent(&proof.challenge) % &self.q;
+            response_sum = (response_sum + &weight * &proof.response) % &self.q;
+
+            let (r1, r2) = &proof.commitments;
+            let (y1, y2) = &proof.public_values;
+            lhs.0 = lhs.0 * r1.modpow(&weight, p) % p;
+            lhs.1 = lhs.1 * r2.modpow(&weight, p) % p;
+            rhs.0 = rhs.0 * y1.modpow(&exponent, p) % p;
+            rhs.1 = rhs.1 * y2.modpow(&exponent, p) % p;
+        }
+
+        lhs.0 == self.alpha.modpow(&response_sum, p) * rhs.0 % p
+            && lhs.1 == self.beta.modpow(&response_sum, p) * rhs.1 % p
+    }
+
+    /// Returns the exclusive bound on challenges: `c_max` if set, else `q`.
+    pub fn challenge_bound(&self) -> &BigUint {
+        self.c_max.as_ref().unwrap_or(&self.q)
+    }
+
+    /// Returns `false` for challenges at or above an explicit `c_max`.
+    ///
+    /// Without `c_max` every challenge is accepted, as before `c_max`
+    /// existed; challenges are then only reduced modulo `q`.
+    fn accepts_challenge(&self, challenge: &BigUint) -> bool {
+        self.c_max.as_ref().is_none_or(|c_max| challenge < c_max)
+    }
+
+    /// Returns `false` if any of `values` takes more bytes than `p`.
+    ///
+    /// Such a value is malformed or hostile; checking the widths costs
+    /// nothing next to the exponentiations it saves during a flood.
+    fn fits_modulus_width(&self, values: [&BigUint; 4]) -> bool {
+        let width = self.p.bits().div_ceil(8);
+        values.iter().all(|v| v.bits().div_ceil(8) <= width)
+    }
+
+    /// Returns the exponent applied to the public values when verifying.
+    ///
+    /// The challenge is reduced modulo `q` first, so the exponent is always
+    /// below or equal to `q` whatever the caller passes. Since the public
+    /// values have order `q` this does not change `y^c`; it keeps the
+    /// challenge space explicit rather than relying on that fact.
+    fn verification_exponent(&self, challenge: &BigUint) -> BigUint {
+        let challenge = self.reduce_q(challenge);
+        match self.convention {
+            ResponseConvention::Subtractive => challenge,
+            ResponseConvention::Additive => &self.q - challenge,
+        }
     }
 }
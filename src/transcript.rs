@@ -0,0 +1,185 @@
+use crate::error::ZKPError;
+use crate::utils::ZKPUtils;
+use num_bigint::BigUint;
+use std::fmt;
+
+/// A complete record of one interactive protocol run.
+///
+/// `Debug` prints the full values; `Display` abbreviates each one to keep
+/// log lines readable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transcript {
+    /// The public values (y1, y2) of the prover.
+    pub public_values: (BigUint, BigUint),
+    /// The commitments (r1, r2).
+    pub commitments: (BigUint, BigUint),
+    /// The challenge `c`.
+    pub challenge: BigUint,
+    /// The response `s`.
+    pub response: BigUint,
+}
+
+impl Transcript {
+    /// The serialization format version written by `to_bytes`.
+    pub const VERSION: u8 = 1;
+
+    /// Serializes the transcript.
+    ///
+    /// The encoding is a version byte followed by `y1`, `y2`, `r1`, `r2`, `c`
+    /// and `s`, each as a 4-byte big-endian length and the big-endian bytes
+    /// of the value.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        ZKPUtils::encode_versioned(Self::VERSION, &self.values())
+    }
+
+    /// Parses a transcript produced by `to_bytes`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Transcript)` if the encoding is well-formed.
+    /// * `Err(ZKPError::UnsupportedProofVersion)` if the version byte is unknown.
+    /// * `Err(ZKPError::MalformedProof)` if the encoding is truncated or has trailing bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ZKPError> {
+        let [y1, y2, r1, r2, challenge, response] =
+            ZKPUtils::decode_versioned(bytes, Self::VERSION)?;
+        Ok(Self {
+            public_values: (y1, y2),
+            commitments: (r1, r2),
+            challenge,
+            response,
+        })
+    }
+
+    /// Returns `[y1, y2, r1, r2, c, s]`.
+    fn values(&self) -> [&BigUint; 6] {
+        [
+            &self.public_values.0,
+            &self.public_values.1,
+            &self.commitments.0,
+            &self.commitments.1,
+            &self.challenge,
+            &self.response,
+        ]
+    }
+}
+
+/// Formats `value` as hex, keeping only the first and last 8 digits of long values.
+fn truncated_hex(value: &BigUint) -> String {
+    let hex = value.to_str_radix(16);
+    if hex.len() <= 16 {
+        hex
+    } else {
+        format!("{}...{}", &hex[..8], &hex[hex.len() - 8..])
+    }
+}
+
+impl fmt::Display for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Transcript {{ y1: {}, y2: {}, r1: {}, r2: {}, c: {}, s: {} }}",
+            truncated_hex(&self.public_values.0),
+            truncated_hex(&self.public_values.1),
+            truncated_hex(&self.commitments.0),
+            truncated_hex(&self.commitments.1),
+            truncated_hex(&self.challenge),
+            truncated_hex(&self.response),
+        )
+    }
+}
+
+/// Serde representations of a `Transcript`, selected by wrapping it.
+///
+/// * `Verbose` is a map of named decimal strings, for configs and debugging:
+///   `{"y1": "123", "y2": "...", "r1": ..., "r2": ..., "c": ..., "s": ...}`.
+/// * `Compact` is a single base64 string of `Transcript::to_bytes`, for
+///   transport.
+///
+/// Both wrap the same `Transcript`, so converting between them is `From`.
+#[cfg(feature = "serde")]
+pub mod repr {
+    use super::Transcript;
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use num_bigint::BigUint;
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Human-readable representation with named decimal fields.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Verbose(pub Transcript);
+
+    /// Compact representation: base64 of the binary codec.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Compact(pub Transcript);
+
+    #[derive(Serialize, Deserialize)]
+    struct VerboseFields {
+        y1: String,
+        y2: String,
+        r1: String,
+        r2: String,
+        c: String,
+        s: String,
+    }
+
+    impl Serialize for Verbose {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let [y1, y2, r1, r2, c, s] = self.0.values().map(BigUint::to_string);
+            VerboseFields {
+                y1,
+                y2,
+                r1,
+                r2,
+                c,
+                s,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Verbose {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let fields = VerboseFields::deserialize(deserializer)?;
+            let parse = |name: &str, value: &str| {
+                value
+                    .parse::<BigUint>()
+                    .map_err(|_| D::Error::custom(format!("`{}` is not a decimal number", name)))
+            };
+            Ok(Verbose(Transcript {
+                public_values: (parse("y1", &fields.y1)?, parse("y2", &fields.y2)?),
+                commitments: (parse("r1", &fields.r1)?, parse("r2", &fields.r2)?),
+                challenge: parse("c", &fields.c)?,
+                response: parse("s", &fields.s)?,
+            }))
+        }
+    }
+
+    impl Serialize for Compact {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&STANDARD.encode(self.0.to_bytes()))
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Compact {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let encoded = String::deserialize(deserializer)?;
+            let bytes = STANDARD.decode(encoded).map_err(D::Error::custom)?;
+            Transcript::from_bytes(&bytes)
+                .map(Compact)
+                .map_err(D::Error::custom)
+        }
+    }
+
+    impl From<Verbose> for Compact {
+        fn from(verbose: Verbose) -> Self {
+            Compact(verbose.0)
+        }
+    }
+
+    impl From<Compact> for Verbose {
+        fn from(compact: Compact) -> Self {
+            Verbose(compact.0)
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::actors::{Prover, Verifier};
+use crate::protocol::ZKPProtocol;
+use crate::secret::SecretProvider;
+use crate::service::RegistrationRecord;
+use num_bigint::BigUint;
+use std::collections::HashMap;
+
+/// The prover's side of the wire for one run of the interactive protocol.
+///
+/// Implementations carry the messages over whatever medium they like
+/// (Cap'n Proto, HTTP, an in-memory channel, ...). The identity of the prover
+/// is a transport concern and is not passed to these methods.
+#[allow(async_fn_in_trait)]
+pub trait ProverTransport {
+    /// The error raised when the underlying transport fails.
+    type Error;
+
+    /// Sends the commitments (r1, r2) to the verifier.
+    async fn send_commitments(
+        &mut self,
+        commitments: (&BigUint, &BigUint),
+    ) -> Result<(), Self::Error>;
+
+    /// Receives the challenge `c` from the verifier.
+    async fn recv_challenge(&mut self) -> Result<BigUint, Self::Error>;
+
+    /// Sends the response `s` to the verifier.
+    async fn send_response(&mut self, response: &BigUint) -> Result<(), Self::Error>;
+
+    /// Receives the verifier's decision.
+    async fn recv_result(&mut self) -> Result<bool, Self::Error>;
+}
+
+/// The verifier's side of the wire, mirroring `ProverTransport`.
+#[allow(async_fn_in_trait)]
+pub trait VerifierTransport {
+    /// The error raised when the underlying transport fails.
+    type Error;
+
+    /// Receives the prover's identity and commitments (r1, r2).
+    async fn recv_commitments(&mut self) -> Result<(String, (BigUint, BigUint)), Self::Error>;
+
+    /// Sends the challenge `c` to the prover.
+    async fn send_challenge(&mut self, challenge: &BigUint) -> Result<(), Self::Error>;
+
+    /// Receives the response `s` from the prover.
+    async fn recv_response(&mut self) -> Result<BigUint, Self::Error>;
+
+    /// Sends the decision to the prover.
+    async fn send_result(&mut self, accepted: bool) -> Result<(), Self::Error>;
+}
+
+/// Looks up the registered public values (y1, y2) of a prover.
+pub trait PublicValueStore {
+    /// Returns the public values registered for `user`, if any.
+    fn public_values(&self, user: &str) -> Option<(BigUint, BigUint)>;
+
+    /// Returns the full registration record of `user`, if the store keeps
+    /// one.
+    ///
+    /// The default returns `None`, for stores that keep only the public
+    /// values.
+    fn record(&self, _user: &str) -> Option<RegistrationRecord> {
+        None
+    }
+}
+
+impl PublicValueStore for HashMap<String, (BigUint, BigUint)> {
+    fn public_values(&self, user: &str) -> Option<(BigUint, BigUint)> {
+        self.get(user).cloned()
+    }
+}
+
+impl PublicValueStore for HashMap<String, RegistrationRecord> {
+    fn public_values(&self, user: &str) -> Option<(BigUint, BigUint)> {
+        self.get(user)
+            .map(|record| (record.y1.clone(), record.y2.clone()))
+    }
+
+    fn record(&self, user: &str) -> Option<RegistrationRecord> {
+        self.get(user).cloned()
+    }
+}
+
+/// Runs the prover's half of one protocol execution over `transport`.
+///
+/// # Returns
+///
+/// * `Ok(true)` if the verifier accepted the proof.
+/// * `Ok(false)` if it was rejected.
+/// * `Err(_)` if the transport failed.
+pub async fn run_prover<T: ZKPProtocol, S: SecretProvider, X: ProverTransport>(
+    prover: &Prover<'_, T, S>,
+    transport: &mut X,
+) -> Result<bool, X::Error> {
+    let ((r1, r2), randomness) = prover.generate_commitments();
+    transport.send_commitments((&r1, &r2)).await?;
+
+    let challenge = transport.recv_challenge().await?;
+    let response = prover.generate_response(&challenge, &randomness);
+    transport.send_response(&response).await?;
+
+    transport.recv_result().await
+}
+
+/// Runs the verifier's half of one protocol execution over `transport`.
+///
+/// Unknown provers still receive a challenge and are rejected at the end,
+/// so the exchange does not reveal which users are registered.
+///
+/// # Returns
+///
+/// * `Ok(accepted)` with the decision that was sent to the prover.
+/// * `Err(_)` if the transport failed.
+pub async fn run_verifier<T: ZKPProtocol, S: PublicValueStore, X: VerifierTransport>(
+    verifier: &Verifier<'_, T>,
+    store: &S,
+    transport: &mut X,
+) -> Result<bool, X::Error> {
+    let (user, (r1, r2)) = transport.recv_commitments().await?;
+    let public_values = store.public_values(&user);
+
+    let challenge = verifier.generate_challenge();
+    transport.send_challenge(&challenge).await?;
+    let response = transport.recv_response().await?;
+
+    let accepted = match public_values {
+        Some((y1, y2)) => verifier.verify((&r1, &r2), &challenge, &response, (&y1, &y2)),
+        None => false,
+    };
+    transport.send_result(accepted).await?;
+
+    Ok(accepted)
+}
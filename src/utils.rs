@@ -1,9 +1,42 @@
+use crate::error::ZKPError;
+use crate::system::{ResponseConvention, ZKPParameters};
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
 use num_bigint::{BigUint, RandBigInt};
-use rand::Rng;
+use rand::{Rng, RngCore};
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::io::{self, Read};
 
 /// Utility functions for the ZKP implementation.
 pub struct ZKPUtils;
 
+/// Format version of sealed secrets.
+const SEALED_SECRET_VERSION: u8 = 1;
+const SEAL_SALT_LEN: usize = 16;
+const SEAL_NONCE_LEN: usize = 12;
+
+/// Miller-Rabin rounds for generated primes.
+const GENERATED_PRIME_ROUNDS: u32 = 40;
+
+thread_local! {
+    // Scratch table of `modpow_multi`, kept per thread so its storage is
+    // reused across verifications instead of reallocated each time.
+    static MULTI_TABLE: RefCell<Vec<BigUint>> = const { RefCell::new(Vec::new()) };
+}
+
+#[cfg(test)]
+thread_local! {
+    // Per thread, so tests running in parallel do not see each other's calls.
+    static MODPOW_MULTI_CALLS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Odd primes used to discard safe-prime candidates before Miller-Rabin.
+const SIEVE_PRIMES: [u32; 24] = [
+    3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+];
+
 impl ZKPUtils {
     /// Generates a cryptographically secure random BigUint below a given bound.
     ///
@@ -19,6 +52,147 @@ impl ZKPUtils {
         rng.gen_biguint_below(bound)
     }
 
+    /// Generates a cryptographically secure random BigUint in `[low, high)`.
+    ///
+    /// The offset from `low` is drawn with `generate_random_below`, which uses
+    /// rejection sampling, so every value in the range is equally likely.
+    ///
+    /// # Arguments
+    ///
+    /// * `low` - The lower bound (inclusive).
+    /// * `high` - The upper bound (exclusive).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `low >= high`.
+    pub fn generate_random_in_range(low: &BigUint, high: &BigUint) -> BigUint {
+        assert!(low < high, "empty range: low must be below high");
+        low + Self::generate_random_below(&(high - low))
+    }
+
+    /// Tests `n` for primality with the Miller-Rabin test.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - The number to test.
+    /// * `rounds` - The number of random bases to try. A composite passes
+    ///   with probability at most `4^-rounds`.
+    ///
+    /// # Returns
+    ///
+    /// `false` if `n` is certainly composite, `true` if it is probably prime.
+    pub fn is_probable_prime(n: &BigUint, rounds: u32) -> bool {
+        let zero = BigUint::from(0u32);
+        let one = BigUint::from(1u32);
+        let two = BigUint::from(2u32);
+        let three = BigUint::from(3u32);
+
+        if *n < two {
+            return false;
+        }
+        if *n <= three {
+            return true;
+        }
+        if n % &two == zero {
+            return false;
+        }
+
+        // n - 1 = d * 2^r with d odd
+        let n_minus_one = n - &one;
+        let r = n_minus_one.trailing_zeros().unwrap_or(0);
+        let d = &n_minus_one >> r;
+
+        'witness: for _ in 0..rounds {
+            let a = Self::generate_random_in_range(&two, &n_minus_one);
+            let mut x = a.modpow(&d, n);
+            if x == one || x == n_minus_one {
+                continue;
+            }
+            for _ in 1..r {
+                x = x.modpow(&two, n);
+                if x == n_minus_one {
+                    continue 'witness;
+                }
+            }
+            return false;
+        }
+        true
+    }
+
+    /// Generates fresh group parameters over a random safe prime.
+    ///
+    /// Searches for `p = 2q + 1` with both `p` and `q` prime and `p` exactly
+    /// `bits` bits long. `alpha` and `beta` are squares of independent random
+    /// values, so they generate the order-`q` subgroup and nobody knows the
+    /// discrete log of one to the other. Expect the search to take seconds at
+    /// 1024 bits and much longer above.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits < 16`.
+    pub fn generate_parameters(bits: u64) -> ZKPParameters {
+        Self::generate_parameters_with_rng(bits, &mut rand::thread_rng())
+    }
+
+    /// Like `generate_parameters`, but draws the candidates and generators
+    /// from `rng`.
+    ///
+    /// With a seeded `rng` the same parameters come out every time, which
+    /// makes benchmarks of the search comparable across runs. The
+    /// Miller-Rabin witnesses still come from the thread RNG; they only
+    /// decide how fast a composite is rejected, not which prime is found.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits < 16`.
+    pub fn generate_parameters_with_rng<R: Rng + ?Sized>(bits: u64, rng: &mut R) -> ZKPParameters {
+        assert!(bits >= 16, "parameters need at least 16 bits");
+
+        let (p, q) = loop {
+            let mut q = rng.gen_biguint(bits - 1);
+            q.set_bit(bits - 2, true);
+            q.set_bit(0, true);
+
+            // Skip q and p = 2q + 1 with a small factor.
+            let has_small_factor = SIEVE_PRIMES.iter().any(|&r| {
+                let m = (&q % r).to_u32_digits().first().copied().unwrap_or(0);
+                m == 0 || (2 * m + 1) % r == 0
+            });
+            if has_small_factor {
+                continue;
+            }
+
+            let p = (&q << 1) + 1u32;
+            if Self::is_probable_prime(&q, GENERATED_PRIME_ROUNDS)
+                && Self::is_probable_prime(&p, GENERATED_PRIME_ROUNDS)
+            {
+                break (p, q);
+            }
+        };
+
+        let one = BigUint::from(1u32);
+        let two = BigUint::from(2u32);
+        let p_minus_one = &p - 1u32;
+        let mut generator = |avoid: &BigUint| loop {
+            let h = rng.gen_biguint_range(&two, &p_minus_one);
+            let g = h.modpow(&two, &p);
+            if g != one && g != *avoid {
+                return g;
+            }
+        };
+        let alpha = generator(&one);
+        let beta = generator(&alpha);
+
+        ZKPParameters {
+            p,
+            q,
+            alpha,
+            beta,
+            convention: ResponseConvention::default(),
+            c_max: None,
+        }
+    }
+
     /// Generates a random alphanumeric string of a given size.
     ///
     /// # Arguments
@@ -36,6 +210,343 @@ impl ZKPUtils {
             .collect()
     }
 
+    /// Returns the canonical wire encoding of `value`.
+    ///
+    /// The canonical form is the minimal big-endian encoding (`[0]` for zero),
+    /// so every number has exactly one accepted byte string.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(bytes)` if the encoding fits in `width` bytes.
+    /// * `None` if `value` is wider than `width` bytes.
+    pub fn canonical_bytes(value: &BigUint, width: usize) -> Option<Vec<u8>> {
+        let bytes = value.to_bytes_be();
+        (bytes.len() <= width).then_some(bytes)
+    }
+
+    /// Decodes a number, accepting only its canonical encoding.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(value)` if `bytes` is exactly `canonical_bytes(value, width)`.
+    /// * `Err(ZKPError::NonCanonicalEncoding)` if `bytes` is empty, has leading
+    ///   zero bytes or is wider than `width` bytes.
+    pub fn decode_canonical(bytes: &[u8], width: usize) -> Result<BigUint, ZKPError> {
+        let value = BigUint::from_bytes_be(bytes);
+        match Self::canonical_bytes(&value, width) {
+            Some(canonical) if canonical == bytes => Ok(value),
+            _ => Err(ZKPError::NonCanonicalEncoding),
+        }
+    }
+
+    /// Decodes a number received from a peer and checks it is below
+    /// `exclusive_max`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(value)` if `bytes` is the canonical encoding of a value below
+    ///   `exclusive_max`.
+    /// * `Err(ZKPError::NonCanonicalEncoding)` if `bytes` is empty, has
+    ///   leading zero bytes or is wider than `exclusive_max`.
+    /// * `Err(ZKPError::ValueOutOfRange)` if the value is `exclusive_max` or
+    ///   more.
+    pub fn bytes_to_biguint_checked(
+        bytes: &[u8],
+        exclusive_max: &BigUint,
+    ) -> Result<BigUint, ZKPError> {
+        let width = exclusive_max.bits().div_ceil(8) as usize;
+        let value = Self::decode_canonical(bytes, width)?;
+        if value >= *exclusive_max {
+            return Err(ZKPError::ValueOutOfRange);
+        }
+        Ok(value)
+    }
+
+    /// Appends `value` as a 4-byte big-endian length followed by its
+    /// big-endian bytes.
+    pub fn write_length_prefixed(out: &mut Vec<u8>, value: &BigUint) {
+        let bytes = value.to_bytes_be();
+        out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        out.extend_from_slice(&bytes);
+    }
+
+    /// Reads one value written by `write_length_prefixed`.
+    ///
+    /// The value is read through `take`, so a forged length cannot make us
+    /// allocate more than the input actually holds.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(value)` if a length and that many bytes were read.
+    /// * `Err` of kind `UnexpectedEof` if the input ends early.
+    /// * `Err` with any other error of `reader`.
+    pub fn read_length_prefixed<R: Read>(reader: &mut R) -> io::Result<BigUint> {
+        let mut len = [0u8; 4];
+        reader.read_exact(&mut len)?;
+        let len = u64::from(u32::from_be_bytes(len));
+        let mut bytes = Vec::new();
+        reader.by_ref().take(len).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(BigUint::from_bytes_be(&bytes))
+    }
+
+    /// Encodes a version byte followed by each of `values` as written by
+    /// `write_length_prefixed`.
+    pub fn encode_versioned(version: u8, values: &[&BigUint]) -> Vec<u8> {
+        let mut out = vec![version];
+        for value in values {
+            Self::write_length_prefixed(&mut out, value);
+        }
+        out
+    }
+
+    /// Decodes exactly `N` values encoded by `encode_versioned`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(values)` if `bytes` is `version` followed by exactly `N` values.
+    /// * `Err(ZKPError::UnsupportedProofVersion)` if the version byte is not
+    ///   `version`.
+    /// * `Err(ZKPError::MalformedProof)` if the encoding is truncated or has
+    ///   trailing bytes.
+    pub fn decode_versioned<const N: usize>(
+        bytes: &[u8],
+        version: u8,
+    ) -> Result<[BigUint; N], ZKPError> {
+        let (&found, mut rest) = bytes.split_first().ok_or(ZKPError::MalformedProof)?;
+        if found != version {
+            return Err(ZKPError::UnsupportedProofVersion(found));
+        }
+        let mut values: [BigUint; N] = std::array::from_fn(|_| BigUint::default());
+        for value in &mut values {
+            *value = Self::read_length_prefixed(&mut rest).map_err(|_| ZKPError::MalformedProof)?;
+        }
+        if !rest.is_empty() {
+            return Err(ZKPError::MalformedProof);
+        }
+        Ok(values)
+    }
+
+    /// Computes the SHA-256 commitment to a challenge value.
+    ///
+    /// # Arguments
+    ///
+    /// * `challenge` - The challenge `c` to commit to.
+    ///
+    /// # Returns
+    ///
+    /// `H(c)` over the big-endian bytes of `c`.
+    pub fn hash_challenge(challenge: &BigUint) -> [u8; 32] {
+        Sha256::digest(challenge.to_bytes_be()).into()
+    }
+
+    /// Derives a Fiat-Shamir challenge by hashing the given values with `D`.
+    ///
+    /// Each value is written as a 4-byte big-endian length followed by its
+    /// big-endian bytes, so distinct inputs cannot collide by concatenation.
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - The group order `q`; the digest is reduced modulo it.
+    /// * `values` - The values bound into the challenge, in order.
+    ///
+    /// # Returns
+    ///
+    /// The challenge `c = H(values) mod q`.
+    pub fn fiat_shamir_challenge<D: Digest>(order: &BigUint, values: &[&BigUint]) -> BigUint {
+        let mut hasher = D::new();
+        for value in values {
+            let bytes = value.to_bytes_be();
+            hasher.update((bytes.len() as u32).to_be_bytes());
+            hasher.update(&bytes);
+        }
+        BigUint::from_bytes_be(&hasher.finalize()) % order
+    }
+
+    /// Derives a Fiat-Shamir challenge bound to a domain, such as
+    /// `ZKPParameters::hash_domain`.
+    ///
+    /// Like `fiat_shamir_challenge`, but the length-prefixed `domain` is hashed
+    /// before the values.
+    ///
+    /// # Returns
+    ///
+    /// The challenge `c = H(domain, values) mod q`.
+    pub fn fiat_shamir_challenge_in_domain<D: Digest>(
+        order: &BigUint,
+        domain: &[u8],
+        values: &[&BigUint],
+    ) -> BigUint {
+        let mut hasher = D::new();
+        hasher.update((domain.len() as u32).to_be_bytes());
+        hasher.update(domain);
+        for value in values {
+            let bytes = value.to_bytes_be();
+            hasher.update((bytes.len() as u32).to_be_bytes());
+            hasher.update(&bytes);
+        }
+        BigUint::from_bytes_be(&hasher.finalize()) % order
+    }
+
+    /// Computes `base_1^exp_1 * ... * base_n^exp_n mod modulus` in one pass.
+    ///
+    /// Uses simultaneous exponentiation (Shamir's trick) with 2-bit windows:
+    /// the products `base_1^d_1 * ... * base_n^d_n` for every choice of digits
+    /// `d_i < 4` are tabulated up front, then a single walk over the exponents
+    /// squares twice and multiplies by one table entry per window. The table
+    /// has `4^n` entries, so this is meant for a handful of terms, such as the
+    /// two in each verification equation.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_exp_pairs` - The `(base, exponent)` terms of the product.
+    /// * `modulus` - The modulus; must not be zero.
+    ///
+    /// # Returns
+    ///
+    /// The product reduced modulo `modulus`; `1 mod modulus` for no terms.
+    pub fn modpow_multi(base_exp_pairs: &[(&BigUint, &BigUint)], modulus: &BigUint) -> BigUint {
+        MULTI_TABLE.with(|table| match table.try_borrow_mut() {
+            Ok(mut table) => Self::modpow_multi_in(&mut table, base_exp_pairs, modulus),
+            Err(_) => Self::modpow_multi_in(&mut Vec::new(), base_exp_pairs, modulus),
+        })
+    }
+
+    /// `modpow_multi` with a fresh table instead of the per-thread one, so
+    /// tests can check that reusing the table does not change results.
+    #[cfg(test)]
+    pub(crate) fn modpow_multi_unpooled(
+        base_exp_pairs: &[(&BigUint, &BigUint)],
+        modulus: &BigUint,
+    ) -> BigUint {
+        Self::modpow_multi_in(&mut Vec::new(), base_exp_pairs, modulus)
+    }
+
+    /// Returns how many times the current thread has run `modpow_multi`, so
+    /// tests can tell whether an operation exponentiated at all.
+    #[cfg(test)]
+    pub(crate) fn modpow_multi_calls_on_this_thread() -> usize {
+        MODPOW_MULTI_CALLS.with(|calls| calls.get())
+    }
+
+    /// Computes `modpow_multi`, building its table in `table`.
+    ///
+    /// Whatever `table` holds is discarded; only its capacity is reused.
+    fn modpow_multi_in(
+        table: &mut Vec<BigUint>,
+        base_exp_pairs: &[(&BigUint, &BigUint)],
+        modulus: &BigUint,
+    ) -> BigUint {
+        const WINDOW: u64 = 2;
+        assert!(*modulus != BigUint::from(0u32), "modulus must not be zero");
+
+        #[cfg(test)]
+        MODPOW_MULTI_CALLS.with(|calls| calls.set(calls.get() + 1));
+
+        // table[d_1 + 4 * d_2 + 16 * d_3 + ...] = base_1^d_1 * base_2^d_2 * ...
+        // Each base extends the table in place: the block for digit d is the
+        // block for d - 1 multiplied by the base once more.
+        table.clear();
+        table.push(BigUint::from(1u32) % modulus);
+        for (base, _) in base_exp_pairs {
+            let base = *base % modulus;
+            let block = table.len();
+            for i in block..block << WINDOW {
+                let entry = &table[i - block] * &base % modulus;
+                table.push(entry);
+            }
+        }
+
+        let bits = base_exp_pairs
+            .iter()
+            .map(|(_, exp)| exp.bits())
+            .max()
+            .unwrap_or(0);
+        let mut acc = table[0].clone();
+        for window in (0..bits.div_ceil(WINDOW)).rev() {
+            for _ in 0..WINDOW {
+                acc = &acc * &acc % modulus;
+            }
+            let index = base_exp_pairs
+                .iter()
+                .enumerate()
+                .map(|(i, (_, exp))| {
+                    let digit = (0..WINDOW)
+                        .filter(|b| exp.bit(window * WINDOW + b))
+                        .fold(0usize, |d, b| d | (1 << b));
+                    digit << (i as u64 * WINDOW)
+                })
+                .fold(0usize, |index, d| index | d);
+            if index != 0 {
+                acc = acc * &table[index] % modulus;
+            }
+        }
+        acc
+    }
+
+    /// Encrypts a secret under a passphrase for storage at rest.
+    ///
+    /// The key is derived with Argon2id (default parameters) from the
+    /// passphrase and a random salt, and the secret is encrypted with
+    /// ChaCha20-Poly1305. The output is
+    /// `version || salt (16) || nonce (12) || ciphertext`.
+    ///
+    /// # Arguments
+    ///
+    /// * `secret` - The secret `x` to protect.
+    /// * `passphrase` - The passphrase to derive the key from.
+    ///
+    /// # Returns
+    ///
+    /// The sealed blob, to be opened with `open_secret`.
+    pub fn seal_secret(secret: &BigUint, passphrase: &str) -> Vec<u8> {
+        let mut salt = [0u8; SEAL_SALT_LEN];
+        let mut nonce = [0u8; SEAL_NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let cipher = Self::seal_cipher(passphrase, &salt);
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), secret.to_bytes_be().as_slice())
+            .expect("ChaCha20-Poly1305 encryption does not fail on small inputs");
+
+        let mut sealed = vec![SEALED_SECRET_VERSION];
+        sealed.extend_from_slice(&salt);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        sealed
+    }
+
+    /// Decrypts a secret sealed with `seal_secret`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(secret)` if the passphrase is right and the data intact.
+    /// * `Err(ZKPError::SecretUnsealFailed)` otherwise.
+    pub fn open_secret(sealed: &[u8], passphrase: &str) -> Result<BigUint, ZKPError> {
+        let (&version, rest) = sealed.split_first().ok_or(ZKPError::SecretUnsealFailed)?;
+        if version != SEALED_SECRET_VERSION || rest.len() < SEAL_SALT_LEN + SEAL_NONCE_LEN {
+            return Err(ZKPError::SecretUnsealFailed);
+        }
+        let (salt, rest) = rest.split_at(SEAL_SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(SEAL_NONCE_LEN);
+
+        let plaintext = Self::seal_cipher(passphrase, salt)
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| ZKPError::SecretUnsealFailed)?;
+        Ok(BigUint::from_bytes_be(&plaintext))
+    }
+
+    fn seal_cipher(passphrase: &str, salt: &[u8]) -> ChaCha20Poly1305 {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .expect("salt and key lengths are within Argon2 limits");
+        ChaCha20Poly1305::new(Key::from_slice(&key))
+    }
+
     /// Returns the 1024-bit MODP Group with 160-bit Prime Order Subgroup constants.
     ///
     /// Reference: [RFC 5114](https://www.rfc-editor.org/rfc/rfc5114#page-15)
@@ -0,0 +1,58 @@
+//! Runs the client binary with `--json` against a server started on a free port.
+
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Command};
+use std::thread::sleep;
+use std::time::Duration;
+
+const BIN: &str = env!("CARGO_BIN_EXE_chaum_pedersen_auth");
+
+struct KillOnDrop(Child);
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn free_addr() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+#[test]
+fn test_client_json_output() {
+    let addr = free_addr();
+    let workdir = std::env::temp_dir().join(format!("cp_auth_json_{}", std::process::id()));
+    std::fs::create_dir_all(&workdir).unwrap();
+
+    let _server = KillOnDrop(
+        Command::new(BIN)
+            .arg("server")
+            .env("AUTH_SERVER_ADDR", &addr)
+            .spawn()
+            .expect("failed to start server"),
+    );
+    for _ in 0..50 {
+        if TcpStream::connect(&addr).is_ok() {
+            break;
+        }
+        sleep(Duration::from_millis(100));
+    }
+
+    let output = Command::new(BIN)
+        .args(["client", "alice", "--json"])
+        .env("AUTH_SERVER_ADDR", &addr)
+        .current_dir(&workdir)
+        .output()
+        .expect("failed to run client");
+    let _ = std::fs::remove_dir_all(&workdir);
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let value: serde_json::Value =
+        serde_json::from_str(stdout.trim()).expect("client output is JSON");
+    assert_eq!(value["status"], "ok");
+    assert_eq!(value["user"], "alice");
+    assert!(!value["session_id"].as_str().unwrap().is_empty());
+}
@@ -0,0 +1,42 @@
+//! Runs the `gen-params` subcommand and builds a system from its output.
+
+use chaum_pedersen_auth::{Prover, Verifier, ZKPProtocol, ZKPSystem, ZKPUtils};
+use num_bigint::BigUint;
+use std::collections::HashMap;
+use std::process::Command;
+
+const BIN: &str = env!("CARGO_BIN_EXE_chaum_pedersen_auth");
+
+#[test]
+fn test_gen_params_output_builds_a_system() {
+    let output = Command::new(BIN)
+        .args(["gen-params", "128"])
+        .output()
+        .expect("failed to run gen-params");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let values: HashMap<&str, BigUint> = stdout
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(name, hex)| (name, BigUint::parse_bytes(hex.as_bytes(), 16).unwrap()))
+        .collect();
+
+    // q is left out on purpose: the builder derives it for a safe prime.
+    let system = ZKPSystem::builder()
+        .with_prime(values["p"].clone())
+        .with_generator(values["alpha"].clone())
+        .with_second_generator(values["beta"].clone())
+        .build()
+        .expect("generated p must be a safe prime");
+    assert_eq!(system.get_order(), &values["q"]);
+    assert_eq!(system.parameters().p.bits(), 128);
+
+    let prover = Prover::new(&system, ZKPUtils::generate_random_below(system.get_order()));
+    let verifier = Verifier::new(&system);
+    let (commitments, randomness) = prover.generate_commitments();
+    let c = verifier.generate_challenge();
+    let s = prover.generate_response(&c, &randomness);
+    let (y1, y2) = prover.public_values();
+    assert!(verifier.verify((&commitments.0, &commitments.1), &c, &s, (y1, y2)));
+}
@@ -0,0 +1,88 @@
+//! Property tests of the prove/verify round trip over a small fixed group.
+
+use chaum_pedersen_auth::{ResponseConvention, ZKPProtocol, ZKPSystem};
+use num_bigint::BigUint;
+use proptest::prelude::*;
+
+/// Safe prime `p = 2q + 1`.
+const P: u64 = 2039;
+/// Prime order of the subgroup of squares.
+const Q: u64 = 1019;
+/// Generators of the order-`q` subgroup (both squares).
+const ALPHA: u64 = 4;
+const BETA: u64 = 9;
+
+fn system(convention: ResponseConvention, precompute: bool) -> ZKPSystem {
+    let mut builder = ZKPSystem::builder()
+        .with_prime(BigUint::from(P))
+        .with_order(BigUint::from(Q))
+        .with_generator(BigUint::from(ALPHA))
+        .with_second_generator(BigUint::from(BETA))
+        .with_response_convention(convention);
+    if precompute {
+        builder = builder.with_precomputation();
+    }
+    builder.build().unwrap()
+}
+
+fn convention() -> impl Strategy<Value = ResponseConvention> {
+    prop_oneof![
+        Just(ResponseConvention::Subtractive),
+        Just(ResponseConvention::Additive),
+    ]
+}
+
+/// Runs one honest protocol execution and returns its transcript as
+/// `[r1, r2, c, s, y1, y2]`.
+fn transcript(system: &ZKPSystem, secret: u64, nonce: u64, challenge: u64) -> [BigUint; 6] {
+    let secret = BigUint::from(secret);
+    let nonce = BigUint::from(nonce);
+    let challenge = BigUint::from(challenge);
+    let (y1, y2) = system.compute_public_values(&secret);
+    let (r1, r2) = system.compute_commitments(&nonce);
+    let s = system.compute_response(&nonce, &challenge, &secret);
+    [r1, r2, challenge, s, y1, y2]
+}
+
+fn verifies(system: &ZKPSystem, t: &[BigUint; 6]) -> bool {
+    system.verify((&t[0], &t[1]), &t[2], &t[3], (&t[4], &t[5]))
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn honest_proof_verifies(
+        convention in convention(),
+        precompute in any::<bool>(),
+        secret in 0..Q,
+        nonce in 0..Q,
+        challenge in 0..Q,
+    ) {
+        let system = system(convention, precompute);
+        prop_assert!(verifies(&system, &transcript(&system, secret, nonce, challenge)));
+    }
+
+    #[test]
+    fn flipped_bit_is_rejected(
+        convention in convention(),
+        precompute in any::<bool>(),
+        secret in 1..Q,
+        nonce in 0..Q,
+        challenge in 1..Q,
+        index in 0usize..6,
+        bit in 0u64..12,
+    ) {
+        let system = system(convention, precompute);
+        let mut t = transcript(&system, secret, nonce, challenge);
+        let flipped = &t[index] ^ (BigUint::from(1u32) << bit);
+        // A flipped public value outside the subgroup can still satisfy the
+        // equations (e.g. y' = -y with an even c); such values are refused at
+        // registration, so only subgroup members are checked here.
+        if index >= 4 && !system.parameters().subgroup_contains(&flipped) {
+            return Ok(());
+        }
+        t[index] = flipped;
+        prop_assert!(!verifies(&system, &t));
+    }
+}